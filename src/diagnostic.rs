@@ -0,0 +1,478 @@
+use std::fmt;
+use std::fmt::Write;
+
+use crate::error::LoxError;
+use crate::scanner::span::Span;
+
+// the kind of an error, which is specific to the phase that found it
+pub trait ErrorKind {
+    // the stable code identifying the error, explained by `--explain`
+    fn code(&self) -> &'static str;
+}
+
+// an error found in the source
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic<K> {
+    pub kind: K,
+    // where in the source it went wrong
+    pub span: Span,
+    // a human readable description of the problem
+    pub message: String,
+    // other places in the source that explain the problem
+    pub labels: Vec<Label>,
+    // a hint on how to fix the problem
+    pub help: Option<String>,
+}
+
+impl<K> Diagnostic<K> {
+    pub fn new(kind: K, span: Span, message: String) -> Diagnostic<K> {
+        Diagnostic {
+            kind,
+            span,
+            message,
+            labels: Vec::new(),
+            help: None,
+        }
+    }
+
+    pub fn with_label(mut self, span: Span, message: &str) -> Diagnostic<K> {
+        self.labels.push(Label::new(span, String::from(message)));
+        self
+    }
+
+    pub fn with_help(mut self, help: &str) -> Diagnostic<K> {
+        self.help = Some(String::from(help));
+        self
+    }
+
+    // the same error with its kind wrapped, like a `LexErrorKind` in a `LoxErrorKind`
+    pub fn map_kind<T>(self, f: impl FnOnce(K) -> T) -> Diagnostic<T> {
+        Diagnostic {
+            kind: f(self.kind),
+            span: self.span,
+            message: self.message,
+            labels: self.labels,
+            help: self.help,
+        }
+    }
+}
+
+impl<K: ErrorKind> Diagnostic<K> {
+    pub fn code(&self) -> &'static str {
+        self.kind.code()
+    }
+}
+
+impl<K> fmt::Display for Diagnostic<K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}] {}", self.span, self.message)
+    }
+}
+
+impl<K: fmt::Debug> std::error::Error for Diagnostic<K> {}
+
+// a second place in the source that helps explain an error, like the opening
+// parenthesis of one that was never closed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+impl Label {
+    pub fn new(span: Span, message: String) -> Label {
+        Label { span, message }
+    }
+}
+
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const RESET: &str = "\x1b[0m";
+
+// renders errors together with the source lines they point at:
+//
+//     error[P0003]: Expect ')' after expression.
+//      --> script.lox:1:9
+//       |
+//     1 | print (1;
+//       |         ^
+//       |       - opening parenthesis here
+pub struct Renderer<'a> {
+    // the name of the file shown in the location line
+    file: &'a str,
+    source: &'a str,
+    // the line the source starts on, which is only past 1 for lines typed at the prompt
+    first_line: usize,
+    // colour the output with ANSI escape codes
+    color: bool,
+}
+
+// one underlined span in the snippet
+struct Annotation<'a> {
+    span: Span,
+    message: &'a str,
+    primary: bool,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(file: &'a str, source: &'a str, color: bool) -> Renderer<'a> {
+        Renderer {
+            file,
+            source,
+            first_line: 1,
+            color,
+        }
+    }
+
+    pub fn with_first_line(mut self, line: usize) -> Renderer<'a> {
+        self.first_line = line;
+        self
+    }
+
+    pub fn render(&self, err: &LoxError) -> String {
+        let span = err.span;
+        let mut annotations = vec![Annotation {
+            span,
+            message: "",
+            primary: true,
+        }];
+        annotations.extend(err.labels.iter().map(|label| Annotation {
+            span: label.span,
+            message: &label.message,
+            primary: false,
+        }));
+        // errors from another source, like a function defined on an earlier line
+        // of the prompt, can't be shown in this one
+        annotations.retain(|annotation| self.line_at(annotation.span).is_some());
+        annotations.sort_by_key(|annotation| (annotation.span.line, !annotation.primary));
+
+        let width = annotations
+            .iter()
+            .map(|annotation| annotation.span.line)
+            .chain([span.line])
+            .max()
+            .unwrap_or_default()
+            .to_string()
+            .len();
+        let gutter = self.paint(BLUE, "|");
+
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{}{}",
+            self.paint(RED, &format!("error[{}]", err.code())),
+            self.paint(BOLD, &format!(": {}", &err.message))
+        );
+        let _ = write!(
+            out,
+            "{:width$}{} {}:{}",
+            "",
+            self.paint(BLUE, "-->"),
+            self.file,
+            span
+        );
+
+        if !annotations.is_empty() {
+            let _ = write!(out, "\n{:width$} {}", "", gutter);
+        }
+        let mut previous_line: Option<usize> = None;
+        for annotation in annotations.iter() {
+            let line = annotation.span.line;
+            let (text, start) = self.line_at(annotation.span).unwrap_or_default();
+            if previous_line != Some(line) {
+                if previous_line.is_some_and(|previous| line > previous + 1) {
+                    let _ = write!(out, "\n{}", self.paint(BLUE, "..."));
+                }
+                let number = self.paint(BLUE, &format!("{:>width$}", line));
+                let _ = write!(out, "\n{} {} {}", number, gutter, text);
+                previous_line = Some(line);
+            }
+
+            // the padding copies tabs from the line so the marks stay aligned
+            let padding: String = text
+                .get(..start)
+                .unwrap_or_default()
+                .chars()
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            let end = annotation
+                .span
+                .end
+                .min(annotation.span.start + text.len() - start);
+            let length = self
+                .source
+                .get(annotation.span.start..end)
+                .map_or(0, |text| text.chars().count())
+                .max(1);
+            let (mark, color) = if annotation.primary {
+                ("^", RED)
+            } else {
+                ("-", BLUE)
+            };
+            let marks = self.paint(color, &mark.repeat(length));
+            let _ = write!(out, "\n{:width$} {} {}{}", "", gutter, padding, marks);
+            if !annotation.message.is_empty() {
+                let _ = write!(out, " {}", self.paint(color, annotation.message));
+            }
+        }
+
+        if let Some(help) = &err.help {
+            let _ = write!(
+                out,
+                "\n{:width$} {} help: {}",
+                "",
+                self.paint(BLUE, "="),
+                help
+            );
+        }
+        out
+    }
+
+    // the error as a JSON object on a single line, for tools to consume. Columns
+    // count characters, and the end is just past the last character of the span.
+    pub fn render_json(&self, err: &LoxError) -> String {
+        let labels: Vec<String> = err
+            .labels
+            .iter()
+            .map(|label| {
+                format!(
+                    "{{\"message\":{},{}}}",
+                    json_string(&label.message),
+                    self.json_span(label.span)
+                )
+            })
+            .collect();
+        format!(
+            "{{\"severity\":\"error\",\"code\":\"{}\",\"message\":{},\"file\":{},{},\"labels\":[{}],\"help\":{}}}",
+            err.code(),
+            json_string(&err.message),
+            json_string(self.file),
+            self.json_span(err.span),
+            labels.join(","),
+            err.help.as_deref().map_or(String::from("null"), json_string)
+        )
+    }
+
+    fn json_span(&self, span: Span) -> String {
+        let (end_line, end_column) = self.end_of(span);
+        format!(
+            "\"start_line\":{},\"start_column\":{},\"end_line\":{},\"end_column\":{}",
+            span.line, span.column, end_line, end_column
+        )
+    }
+
+    // the line and column just past the end of the span
+    fn end_of(&self, span: Span) -> (usize, usize) {
+        let text = self
+            .line_at(span)
+            .and(self.source.get(span.start..span.end));
+        let Some(text) = text else {
+            return (span.line, span.column);
+        };
+        match text.rfind('\n') {
+            Some(i) => (
+                span.line + text.matches('\n').count(),
+                text[i + 1..].chars().count() + 1,
+            ),
+            None => (span.line, span.column + text.chars().count()),
+        }
+    }
+
+    // the source line holding the start of the span, and the byte offset of the
+    // start within it. Spans whose line doesn't match point into some other source.
+    fn line_at(&self, span: Span) -> Option<(&'a str, usize)> {
+        let before = self.source.get(..span.start)?;
+        if self.first_line + before.matches('\n').count() != span.line {
+            return None;
+        }
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let line_end = self.source[span.start..]
+            .find('\n')
+            .map_or(self.source.len(), |i| span.start + i);
+        let line = self.source[line_start..line_end].trim_end_matches('\r');
+        Some((line, (span.start - line_start).min(line.len())))
+    }
+
+    fn paint(&self, color: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", color, text, RESET)
+        } else {
+            String::from(text)
+        }
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::error::RuntimeError;
+    use crate::interpreter::error::RuntimeErrorKind;
+    use crate::scanner::lexer::scan_tokens;
+    use crate::scanner::lexer::scan_tokens_with_diagnostics;
+    use crate::syntax_tree::parser::Parser;
+
+    fn parse_errors(source: &str) -> Vec<LoxError> {
+        let (_, errors) = Parser::new(scan_tokens(source).unwrap()).parse();
+        errors.into_iter().map(LoxError::from).collect()
+    }
+
+    #[test]
+    fn test_render_snippet_with_label() {
+        let source = "var a = 1;\nprint (a + 1;\n";
+        let errors = parse_errors(source);
+
+        let rendered = Renderer::new("test.lox", source, false).render(&errors[0]);
+
+        assert_eq!(
+            rendered,
+            "error[P0003]: Expect ')' after expression.\n \
+             --> test.lox:2:13\n  \
+               |\n\
+             2 | print (a + 1;\n  \
+               |             ^\n  \
+               |       - opening parenthesis here"
+        );
+    }
+
+    #[test]
+    fn test_render_labels_on_other_lines() {
+        let source = "{\n  print 1;\n\n\n\n\n\n\n  print 2;\n";
+        let errors = parse_errors(source);
+
+        let rendered = Renderer::new("test.lox", source, false).render(&errors[0]);
+
+        assert_eq!(
+            rendered,
+            "error[P0005]: Expect '}' after block.\n  \
+              --> test.lox:10:1\n   \
+                |\n \
+              1 | {\n   \
+                | - block opened here\n\
+             ...\n\
+             10 | \n   \
+                | ^"
+        );
+    }
+
+    #[test]
+    fn test_render_underlines_whole_span_and_help() {
+        let source = "print \"abc;\n";
+        let (_, diagnostics) = scan_tokens_with_diagnostics(source);
+        let err = LoxError::from(diagnostics[0].clone());
+
+        let rendered = Renderer::new("test.lox", source, false).render(&err);
+
+        assert_eq!(
+            rendered,
+            "error[L0001]: Unterminated string\n \
+             --> test.lox:1:7\n  \
+               |\n\
+             1 | print \"abc;\n  \
+               |       ^^^^^\n  \
+               = help: add a closing '\"' to end the string"
+        );
+    }
+
+    #[test]
+    fn test_render_with_color() {
+        let source = "print 1 +;";
+        let errors = parse_errors(source);
+
+        let rendered = Renderer::new("test.lox", source, true).render(&errors[0]);
+
+        assert!(
+            rendered.starts_with("\x1b[1;31merror[P0001]\x1b[0m\x1b[1m: Expect expression.\x1b[0m")
+        );
+        assert!(rendered.contains("\x1b[1;31m^\x1b[0m"));
+    }
+
+    #[test]
+    fn test_render_span_outside_source() {
+        let source = "1;";
+        let err = parse_errors("\n\nprint (1;").remove(0);
+
+        let rendered = Renderer::new("<stdin>", source, false).render(&err);
+
+        assert_eq!(
+            rendered,
+            "error[P0003]: Expect ')' after expression.\n --> <stdin>:3:9"
+        );
+    }
+
+    #[test]
+    fn test_render_span_from_another_source() {
+        // a runtime error in a function declared on the first line of the prompt,
+        // raised while running the second
+        let first = "fun f() { return -nil; }";
+        let second = "print \"abcdefghijé\"; f();";
+        let err = LoxError::from(RuntimeError::new(
+            RuntimeErrorKind::TypeError,
+            Span::new(17, 18, 1, 18),
+            String::from("Operand must be a number."),
+        ));
+
+        let renderer = Renderer::new("<stdin>", second, false).with_first_line(2);
+
+        assert_eq!(
+            renderer.render(&err),
+            "error[E0001]: Operand must be a number.\n --> <stdin>:1:18"
+        );
+        assert!(renderer
+            .render_json(&err)
+            .contains("\"end_line\":1,\"end_column\":18"));
+        let rendered = Renderer::new("<stdin>", first, false).render(&err);
+        assert!(rendered.ends_with("1 | fun f() { return -nil; }\n  |                  ^"));
+    }
+
+    #[test]
+    fn test_render_json() {
+        let source = "var a = 1;\nprint (a + 1;\n";
+        let errors = parse_errors(source);
+
+        let rendered = Renderer::new("dir/test.lox", source, true).render_json(&errors[0]);
+
+        assert_eq!(
+            rendered,
+            "{\"severity\":\"error\",\"code\":\"P0003\",\"message\":\"Expect ')' after expression.\",\
+             \"file\":\"dir/test.lox\",\"start_line\":2,\"start_column\":13,\"end_line\":2,\"end_column\":14,\
+             \"labels\":[{\"message\":\"opening parenthesis here\",\
+             \"start_line\":2,\"start_column\":7,\"end_line\":2,\"end_column\":8}],\"help\":null}"
+        );
+    }
+
+    #[test]
+    fn test_render_json_escapes_strings_and_spans_lines() {
+        let source = "print \"a\\tb\nc";
+        let (_, diagnostics) = scan_tokens_with_diagnostics(source);
+        let err = LoxError::from(diagnostics[0].clone());
+
+        let rendered = Renderer::new("C:\\test.lox", source, false).render_json(&err);
+
+        assert_eq!(
+            rendered,
+            "{\"severity\":\"error\",\"code\":\"L0001\",\"message\":\"Unterminated string\",\
+             \"file\":\"C:\\\\test.lox\",\"start_line\":1,\"start_column\":7,\"end_line\":2,\"end_column\":2,\
+             \"labels\":[],\"help\":\"add a closing '\\\"' to end the string\"}"
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use crate::diagnostic::Diagnostic;
+use crate::diagnostic::ErrorKind;
+use crate::interpreter::error::RuntimeError;
+use crate::interpreter::error::RuntimeErrorKind;
+use crate::resolver::error::ResolveError;
+use crate::resolver::error::ResolveErrorKind;
+use crate::scanner::error::LexError;
+use crate::scanner::error::LexErrorKind;
+use crate::syntax_tree::error::ParseError;
+use crate::syntax_tree::error::ParseErrorKind;
+
+// the kind of every error the interpreter can report, grouped by the phase that
+// produced it
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoxErrorKind {
+    Lex(LexErrorKind),
+    Parse(ParseErrorKind),
+    Resolve(ResolveErrorKind),
+    Runtime(RuntimeErrorKind),
+}
+
+impl ErrorKind for LoxErrorKind {
+    // `L` for scanning, `P` for parsing, `R` for resolving and `E` for runtime errors
+    fn code(&self) -> &'static str {
+        match self {
+            LoxErrorKind::Lex(kind) => kind.code(),
+            LoxErrorKind::Parse(kind) => kind.code(),
+            LoxErrorKind::Resolve(kind) => kind.code(),
+            LoxErrorKind::Runtime(kind) => kind.code(),
+        }
+    }
+}
+
+pub type LoxError = Diagnostic<LoxErrorKind>;
+
+impl From<LexError> for LoxError {
+    fn from(err: LexError) -> LoxError {
+        err.map_kind(LoxErrorKind::Lex)
+    }
+}
+
+impl From<ParseError> for LoxError {
+    fn from(err: ParseError) -> LoxError {
+        err.map_kind(LoxErrorKind::Parse)
+    }
+}
+
+impl From<RuntimeError> for LoxError {
+    fn from(err: RuntimeError) -> LoxError {
+        err.map_kind(LoxErrorKind::Runtime)
+    }
+}
+
+impl From<ResolveError> for LoxError {
+    fn from(err: ResolveError) -> LoxError {
+        err.map_kind(LoxErrorKind::Resolve)
+    }
+}
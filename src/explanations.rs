@@ -0,0 +1,650 @@
+// long-form explanations of the error codes, shown by `--explain`. Codes are
+// stable: once given out, a code keeps its meaning and is never reused.
+const EXPLANATIONS: &[(&str, &str)] = &[
+    (
+        "L0001",
+        "A string literal was never closed.
+
+Strings start and end with `\"` and may span several lines, so a missing
+closing quote swallows the rest of the file.
+
+Erroneous example:
+
+    print \"hello;
+
+Add the closing quote:
+
+    print \"hello\";
+",
+    ),
+    (
+        "L0002",
+        "A character that can't start any token was found.
+
+Outside of strings and comments, Lox only uses letters, digits, `_` and the
+characters `(){},.-+;*/!=<>\"`.
+
+Erroneous example:
+
+    var total = price # 2;
+
+Use one of the operators Lox has:
+
+    var total = price * 2;
+",
+    ),
+    (
+        "L0003",
+        "A block comment was never closed.
+
+Block comments nest, so every `/*`, including ones inside the comment, needs a
+matching `*/`.
+
+Erroneous example:
+
+    /* outer /* inner */
+    print 1;
+
+Close both comments:
+
+    /* outer /* inner */ */
+    print 1;
+",
+    ),
+    (
+        "L0004",
+        "A number literal is malformed.
+
+Numbers are decimal with an optional fraction and exponent, or integers with a
+`0x`, `0o` or `0b` prefix. A `.` or exponent must be followed by digits, the
+digits must fit the prefix, and `_` separators may only go between digits or
+right after a prefix, as in `0x_FF`.
+
+Erroneous example:
+
+    var mask = 0b102;
+    var large = 1__000;
+
+Use only valid digits and single separators between them:
+
+    var mask = 0b101;
+    var large = 1_000;
+",
+    ),
+    (
+        "L0005",
+        "A string contains an invalid escape sequence.
+
+The escapes Lox knows are `\\n`, `\\t`, `\\r`, `\\0`, `\\\\`, `\\\"` and
+`\\u{...}` with one to six hexadecimal digits naming a unicode scalar value.
+
+Erroneous example:
+
+    print \"C:\\temp\\quotes\";
+
+Escape the backslashes:
+
+    print \"C:\\\\temp\\\\quotes\";
+",
+    ),
+    (
+        "P0001",
+        "An expression was expected.
+
+The parser reached a token that can't start an expression, often because an
+operand is missing.
+
+Erroneous example:
+
+    var a = 1 + ;
+
+Supply the missing operand:
+
+    var a = 1 + 2;
+",
+    ),
+    (
+        "P0002",
+        "A `;` was expected.
+
+Expression, `print`, `var` and `return` statements, as well as the first two
+clauses of a `for` loop, end with a semicolon.
+
+Erroneous example:
+
+    print \"hello\"
+    print \"world\";
+
+Terminate the statement:
+
+    print \"hello\";
+    print \"world\";
+",
+    ),
+    (
+        "P0003",
+        "A closing `)` was expected.
+
+A parenthesis was opened around a grouped expression, the arguments of a
+call, the parameters of a function or the clauses of `if`, `while` or `for`,
+but something else was found where it should be closed. The error points at
+the opening parenthesis as well.
+
+Erroneous example:
+
+    print (1 + 2;
+
+Close the parenthesis:
+
+    print (1 + 2);
+",
+    ),
+    (
+        "P0004",
+        "An opening `(` was expected.
+
+The conditions of `if` and `while`, the clauses of `for` and the parameters of
+a function or method are written in parentheses.
+
+Erroneous example:
+
+    if ready print \"go\";
+
+Put the condition in parentheses:
+
+    if (ready) print \"go\";
+",
+    ),
+    (
+        "P0005",
+        "A closing `}` was expected.
+
+A block or class body was opened but the file ended before it was closed. The
+error points at the opening brace as well.
+
+Erroneous example:
+
+    fun greet() {
+        print \"hi\";
+
+Close the block:
+
+    fun greet() {
+        print \"hi\";
+    }
+",
+    ),
+    (
+        "P0006",
+        "An opening `{` was expected.
+
+Function, method and class bodies are blocks and need braces, even when they
+are a single statement.
+
+Erroneous example:
+
+    fun twice(x) return x * 2;
+
+Wrap the body in braces:
+
+    fun twice(x) { return x * 2; }
+",
+    ),
+    (
+        "P0007",
+        "A name was expected.
+
+Declarations of variables, functions, classes and parameters, superclasses,
+property accesses after `.` and methods after `super.` all need an
+identifier. Keywords can't be used as names.
+
+Erroneous example:
+
+    var class = \"lox\";
+
+Pick a name that isn't a keyword:
+
+    var kind = \"lox\";
+",
+    ),
+    (
+        "P0008",
+        "A `.` was expected after `super`.
+
+`super` can only be used to access a method of the superclass.
+
+Erroneous example:
+
+    class B < A {
+        m() { return super; }
+    }
+
+Name the method:
+
+    class B < A {
+        m() { return super.m(); }
+    }
+",
+    ),
+    (
+        "P0009",
+        "The end of the input was expected.
+
+Input that is parsed as a single expression, like a line typed at the prompt
+to have its value printed, continued after the end of the expression.
+
+Erroneous example:
+
+    > 1 + 2 3
+
+Enter one expression at a time:
+
+    > 1 + 2
+",
+    ),
+    (
+        "P0010",
+        "The left-hand side of `=` can't be assigned to.
+
+Only variables and fields of instances can be assigned to.
+
+Erroneous example:
+
+    a + b = c;
+
+Assign to a variable or a field:
+
+    a = c - b;
+    point.x = c;
+",
+    ),
+    (
+        "P0011",
+        "A call or function declaration has too many arguments.
+
+Functions take at most 255 parameters and calls pass at most 255 arguments.
+
+Erroneous example:
+
+    fun f(a1, a2, ..., a256) {}
+
+Group related values into an instance of a class and pass that instead.
+",
+    ),
+    (
+        "P0012",
+        "An expression is nested too deeply.
+
+Expressions may nest at most 256 levels deep, counting every operand, unary
+operator and parenthesis that encloses another expression. Deeper nesting is
+rejected so that compiling and running the expression can't exhaust the stack.
+
+Erroneous example:
+
+    print ((((((...(1)...))))));
+
+Split the expression up, keeping inner parts in variables:
+
+    var inner = (((1)));
+    print (((inner)));
+",
+    ),
+    (
+        "R0001",
+        "A local variable is read in its own initializer.
+
+The new variable shadows any outer one with the same name as soon as it is
+declared, so its initializer would read the variable before it has a value.
+
+Erroneous example:
+
+    var a = 1;
+    {
+        var a = a + 1;
+    }
+
+Give the inner variable another name:
+
+    var a = 1;
+    {
+        var b = a + 1;
+    }
+",
+    ),
+    (
+        "R0002",
+        "A variable is declared twice in the same local scope.
+
+Global variables may be redeclared, but in a block or function every name can
+be declared only once.
+
+Erroneous example:
+
+    fun f() {
+        var a = 1;
+        var a = 2;
+    }
+
+Assign to the existing variable instead:
+
+    fun f() {
+        var a = 1;
+        a = 2;
+    }
+",
+    ),
+    (
+        "R0003",
+        "`return` is used outside of a function.
+
+Erroneous example:
+
+    return 1;
+
+Only return from inside a function or method:
+
+    fun one() {
+        return 1;
+    }
+",
+    ),
+    (
+        "R0004",
+        "`this` is used outside of a class.
+
+`this` refers to the instance a method was called on, so it only exists
+inside methods.
+
+Erroneous example:
+
+    fun name() { return this.name; }
+
+Make the function a method:
+
+    class Person {
+        name() { return this.name; }
+    }
+",
+    ),
+    (
+        "R0005",
+        "`super` is used outside of a class.
+
+`super` refers to the methods of the superclass of the class it appears in.
+
+Erroneous example:
+
+    super.init();
+
+Only use `super` inside the methods of a subclass.
+",
+    ),
+    (
+        "R0006",
+        "`super` is used in a class without a superclass.
+
+Erroneous example:
+
+    class A {
+        m() { return super.m(); }
+    }
+
+Inherit from the class whose method should be called:
+
+    class A < Base {
+        m() { return super.m(); }
+    }
+",
+    ),
+    (
+        "R0007",
+        "A class inherits from itself.
+
+Erroneous example:
+
+    class A < A {}
+
+Inherit from another class:
+
+    class A < Base {}
+",
+    ),
+    (
+        "R0008",
+        "An initializer returns a value.
+
+`init` always returns the instance it initialized, so its `return` statements
+may not have a value. A bare `return;` leaves it early.
+
+Erroneous example:
+
+    class Point {
+        init(x) { return x; }
+    }
+
+Leave the initializer without a value:
+
+    class Point {
+        init(x) {
+            this.x = x;
+            return;
+        }
+    }
+",
+    ),
+    (
+        "E0001",
+        "An operator was applied to values of the wrong type.
+
+Arithmetic and comparison operators need numbers, and `+` needs two numbers
+or two strings. Values are never converted implicitly.
+
+Erroneous example:
+
+    print \"total: \" + 3;
+
+Operands of `+` must both be strings or both be numbers:
+
+    print \"total: \" + \"3\";
+",
+    ),
+    (
+        "E0002",
+        "A variable was used that has not been defined.
+
+Global variables have to be declared with `var` before the code that uses them
+runs.
+
+Erroneous example:
+
+    print count;
+    var count = 1;
+
+Declare the variable first:
+
+    var count = 1;
+    print count;
+",
+    ),
+    (
+        "E0003",
+        "Something other than a function or class was called.
+
+Erroneous example:
+
+    var name = \"lox\";
+    name();
+
+Only call functions, methods and classes.
+",
+    ),
+    (
+        "E0004",
+        "A function was called with the wrong number of arguments.
+
+Calls have to pass exactly as many arguments as the function has parameters.
+Calling a class passes the arguments to its `init` method.
+
+Erroneous example:
+
+    fun add(a, b) { return a + b; }
+    add(1);
+
+Pass every argument:
+
+    add(1, 2);
+",
+    ),
+    (
+        "E0005",
+        "A property was accessed on a value that isn't an instance.
+
+Only instances of classes have properties and fields.
+
+Erroneous example:
+
+    var point = 3;
+    point.x = 1;
+
+Create an instance first:
+
+    class Point {}
+    var point = Point();
+    point.x = 1;
+",
+    ),
+    (
+        "E0006",
+        "An instance has no field or method with the given name.
+
+Fields exist once they are assigned, and methods have to be declared in the
+class or one of its superclasses.
+
+Erroneous example:
+
+    class Point {}
+    print Point().x;
+
+Assign the field before reading it:
+
+    class Point {
+        init() { this.x = 0; }
+    }
+    print Point().x;
+",
+    ),
+    (
+        "E0007",
+        "A class inherits from something that isn't a class.
+
+Erroneous example:
+
+    var Base = \"base\";
+    class A < Base {}
+
+Inherit from a class:
+
+    class Base {}
+    class A < Base {}
+",
+    ),
+    (
+        "E0008",
+        "Calls were nested too deeply.
+
+The interpreter stops a program once 1000 calls are in progress at the same
+time, which almost always means a recursive function is missing its base case.
+
+Erroneous example:
+
+    fun countdown(n) {
+        print n;
+        countdown(n - 1);
+    }
+    countdown(3);
+
+Stop the recursion:
+
+    fun countdown(n) {
+        if (n < 0) return;
+        print n;
+        countdown(n - 1);
+    }
+    countdown(3);
+",
+    ),
+];
+
+// the explanation of an error code like `P0003`, in either case
+pub fn explain(code: &str) -> Option<&'static str> {
+    let code = code.to_uppercase();
+    EXPLANATIONS
+        .iter()
+        .find(|(known, _)| *known == code)
+        .map(|(_, explanation)| *explanation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostic::ErrorKind;
+    use crate::interpreter::error::RuntimeErrorKind;
+    use crate::resolver::error::ResolveErrorKind;
+    use crate::scanner::error::LexErrorKind;
+    use crate::syntax_tree::error::Expected;
+    use crate::syntax_tree::error::ParseErrorKind;
+
+    #[test]
+    fn test_every_code_is_explained_once() {
+        let codes = [
+            LexErrorKind::UnterminatedString.code(),
+            LexErrorKind::UnrecognizedCharacter.code(),
+            LexErrorKind::UnterminatedComment.code(),
+            LexErrorKind::InvalidNumber.code(),
+            LexErrorKind::InvalidEscape.code(),
+            ParseErrorKind::ExpectedExpression.code(),
+            ParseErrorKind::ExpectedToken(Expected::Semicolon).code(),
+            ParseErrorKind::ExpectedToken(Expected::RightParen).code(),
+            ParseErrorKind::ExpectedToken(Expected::LeftParen).code(),
+            ParseErrorKind::ExpectedToken(Expected::RightBrace).code(),
+            ParseErrorKind::ExpectedToken(Expected::LeftBrace).code(),
+            ParseErrorKind::ExpectedToken(Expected::Identifier).code(),
+            ParseErrorKind::ExpectedToken(Expected::Dot).code(),
+            ParseErrorKind::ExpectedToken(Expected::Eof).code(),
+            ParseErrorKind::InvalidAssignmentTarget.code(),
+            ParseErrorKind::TooManyArguments.code(),
+            ParseErrorKind::TooDeeplyNested.code(),
+            ResolveErrorKind::ReadInOwnInitializer.code(),
+            ResolveErrorKind::AlreadyDeclared.code(),
+            ResolveErrorKind::TopLevelReturn.code(),
+            ResolveErrorKind::ThisOutsideClass.code(),
+            ResolveErrorKind::SuperOutsideClass.code(),
+            ResolveErrorKind::SuperWithoutSuperclass.code(),
+            ResolveErrorKind::InheritFromSelf.code(),
+            ResolveErrorKind::ReturnValueFromInitializer.code(),
+            RuntimeErrorKind::TypeError.code(),
+            RuntimeErrorKind::UndefinedVariable.code(),
+            RuntimeErrorKind::NotCallable.code(),
+            RuntimeErrorKind::ArityMismatch.code(),
+            RuntimeErrorKind::NotAnInstance.code(),
+            RuntimeErrorKind::UndefinedProperty.code(),
+            RuntimeErrorKind::InvalidSuperclass.code(),
+            RuntimeErrorKind::StackOverflow.code(),
+        ];
+
+        for code in codes {
+            assert!(explain(code).is_some(), "{} is not explained", code);
+        }
+        let explained: Vec<&str> = EXPLANATIONS.iter().map(|(code, _)| *code).collect();
+        assert_eq!(explained, codes);
+    }
+
+    #[test]
+    fn test_explain_ignores_case() {
+        assert_eq!(explain("p0003"), explain("P0003"));
+        assert!(explain("P0003")
+            .unwrap()
+            .starts_with("A closing `)` was expected."));
+        assert_eq!(explain("X9999"), None);
+    }
+}
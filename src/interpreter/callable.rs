@@ -0,0 +1,17 @@
+use std::fmt;
+
+use crate::interpreter::error::RuntimeError;
+use crate::interpreter::value::Value;
+use crate::interpreter::Interpreter;
+
+// anything that can be called from Lox code, whether written in Lox or in Rust
+pub trait Callable<'src>: fmt::Debug + fmt::Display {
+    // the number of arguments the callable expects
+    fn arity(&self) -> usize;
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter<'src>,
+        arguments: Vec<Value<'src>>,
+    ) -> Result<Value<'src>, RuntimeError>;
+}
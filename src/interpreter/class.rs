@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::interpreter::callable::Callable;
+use crate::interpreter::error::RuntimeError;
+use crate::interpreter::function::LoxFunction;
+use crate::interpreter::instance::LoxInstance;
+use crate::interpreter::value::Value;
+use crate::interpreter::Interpreter;
+
+#[derive(Debug)]
+pub struct LoxClass<'src> {
+    pub name: String,
+    pub superclass: Option<Rc<LoxClass<'src>>>,
+    methods: HashMap<String, Rc<LoxFunction<'src>>>,
+}
+
+impl<'src> LoxClass<'src> {
+    pub fn new(
+        name: String,
+        superclass: Option<Rc<LoxClass<'src>>>,
+        methods: HashMap<String, Rc<LoxFunction<'src>>>,
+    ) -> LoxClass<'src> {
+        LoxClass {
+            name,
+            superclass,
+            methods,
+        }
+    }
+
+    // looks the method up on this class, then on its superclasses
+    pub fn find_method(&self, name: &str) -> Option<Rc<LoxFunction<'src>>> {
+        match self.methods.get(name) {
+            Some(method) => Some(Rc::clone(method)),
+            None => self
+                .superclass
+                .as_ref()
+                .and_then(|superclass| superclass.find_method(name)),
+        }
+    }
+
+    // a class takes the same arguments as its initializer
+    pub fn arity(&self) -> usize {
+        self.find_method("init")
+            .map(|init| init.arity())
+            .unwrap_or(0)
+    }
+
+    // calling a class creates a new instance and runs its initializer on it
+    pub fn instantiate(
+        class: &Rc<LoxClass<'src>>,
+        interpreter: &mut Interpreter<'src>,
+        arguments: Vec<Value<'src>>,
+    ) -> Result<Value<'src>, RuntimeError> {
+        let instance = Rc::new(LoxInstance::new(Rc::clone(class)));
+        if let Some(init) = class.find_method("init") {
+            init.bind(Rc::clone(&instance))
+                .call(interpreter, arguments)?;
+        }
+        Ok(Value::Instance(instance))
+    }
+}
+
+impl<'src> fmt::Display for LoxClass<'src> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
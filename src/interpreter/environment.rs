@@ -0,0 +1,101 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::interpreter::error::RuntimeError;
+use crate::interpreter::error::RuntimeErrorKind;
+use crate::interpreter::value::Value;
+use crate::scanner::token::Token;
+
+#[derive(Debug, Default)]
+pub struct Environment<'src> {
+    values: HashMap<String, Value<'src>>,
+    // the surrounding scope, `None` for the global scope
+    enclosing: Option<Rc<RefCell<Environment<'src>>>>,
+}
+
+impl<'src> Environment<'src> {
+    pub fn new() -> Environment<'src> {
+        Environment::default()
+    }
+
+    pub fn with_enclosing(enclosing: Rc<RefCell<Environment<'src>>>) -> Environment<'src> {
+        Environment {
+            values: HashMap::new(),
+            enclosing: Some(enclosing),
+        }
+    }
+
+    // defining an existing name again simply overwrites it
+    pub fn define(&mut self, name: String, value: Value<'src>) {
+        self.values.insert(name, value);
+    }
+
+    pub fn get(&self, name: &Token) -> Result<Value<'src>, RuntimeError> {
+        self.lookup(name.name())
+            .ok_or_else(|| undefined_variable(name))
+    }
+
+    // finds a variable by name, walking outwards through the enclosing scopes
+    pub fn lookup(&self, name: &str) -> Option<Value<'src>> {
+        if let Some(value) = self.values.get(name) {
+            return Some(value.clone());
+        }
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow().lookup(name),
+            None => None,
+        }
+    }
+
+    // reads a variable from the scope `distance` steps out, as computed by the resolver
+    pub fn get_at(&self, distance: usize, name: &str) -> Option<Value<'src>> {
+        if distance == 0 {
+            return self.values.get(name).cloned();
+        }
+        self.enclosing
+            .as_ref()
+            .and_then(|enclosing| enclosing.borrow().get_at(distance - 1, name))
+    }
+
+    pub fn assign_at(
+        &mut self,
+        distance: usize,
+        name: &Token,
+        value: Value<'src>,
+    ) -> Result<(), RuntimeError> {
+        if distance == 0 {
+            return match self.values.get_mut(name.name()) {
+                Some(slot) => {
+                    *slot = value;
+                    Ok(())
+                }
+                None => Err(undefined_variable(name)),
+            };
+        }
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow_mut().assign_at(distance - 1, name, value),
+            None => Err(undefined_variable(name)),
+        }
+    }
+
+    // unlike `define`, assigning never creates a new variable
+    pub fn assign(&mut self, name: &Token, value: Value<'src>) -> Result<(), RuntimeError> {
+        if let Some(slot) = self.values.get_mut(name.name()) {
+            *slot = value;
+            return Ok(());
+        }
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow_mut().assign(name, value),
+            None => Err(undefined_variable(name)),
+        }
+    }
+}
+
+pub(super) fn undefined_variable(name: &Token) -> RuntimeError {
+    RuntimeError::new(
+        RuntimeErrorKind::UndefinedVariable,
+        name.span,
+        format!("Undefined variable '{}'.", name.name()),
+    )
+    .with_help("variables have to be declared with 'var' before they are used")
+}
@@ -0,0 +1,38 @@
+use crate::diagnostic::Diagnostic;
+use crate::diagnostic::ErrorKind;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeErrorKind {
+    // an operator was applied to a value of the wrong type
+    TypeError,
+    UndefinedVariable,
+    // the callee of a call expression is not a function
+    NotCallable,
+    // a function was called with the wrong number of arguments
+    ArityMismatch,
+    // a property was accessed on something that isn't an instance
+    NotAnInstance,
+    UndefinedProperty,
+    // a class tried to inherit from something that isn't a class
+    InvalidSuperclass,
+    // calls nested deeper than the interpreter allows, usually unbounded recursion
+    StackOverflow,
+}
+
+impl ErrorKind for RuntimeErrorKind {
+    fn code(&self) -> &'static str {
+        match self {
+            RuntimeErrorKind::TypeError => "E0001",
+            RuntimeErrorKind::UndefinedVariable => "E0002",
+            RuntimeErrorKind::NotCallable => "E0003",
+            RuntimeErrorKind::ArityMismatch => "E0004",
+            RuntimeErrorKind::NotAnInstance => "E0005",
+            RuntimeErrorKind::UndefinedProperty => "E0006",
+            RuntimeErrorKind::InvalidSuperclass => "E0007",
+            RuntimeErrorKind::StackOverflow => "E0008",
+        }
+    }
+}
+
+// an error raised while running, pointing at the operator or name that caused it
+pub type RuntimeError = Diagnostic<RuntimeErrorKind>;
@@ -0,0 +1,94 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::interpreter::callable::Callable;
+use crate::interpreter::environment::Environment;
+use crate::interpreter::error::RuntimeError;
+use crate::interpreter::instance::LoxInstance;
+use crate::interpreter::value::Value;
+use crate::interpreter::Interpreter;
+use crate::interpreter::Interrupt;
+use crate::syntax_tree::statement::FunctionDecl;
+
+pub struct LoxFunction<'src> {
+    declaration: Rc<FunctionDecl<'src>>,
+    // the environment the function was declared in
+    closure: Rc<RefCell<Environment<'src>>>,
+    // initializers always return `this`
+    is_initializer: bool,
+}
+
+impl<'src> LoxFunction<'src> {
+    pub fn new(
+        declaration: Rc<FunctionDecl<'src>>,
+        closure: Rc<RefCell<Environment<'src>>>,
+        is_initializer: bool,
+    ) -> LoxFunction<'src> {
+        LoxFunction {
+            declaration,
+            closure,
+            is_initializer,
+        }
+    }
+
+    // creates a copy of the method whose `this` refers to the given instance
+    pub fn bind(&self, instance: Rc<LoxInstance<'src>>) -> LoxFunction<'src> {
+        let mut environment = Environment::with_enclosing(Rc::clone(&self.closure));
+        environment.define(String::from("this"), Value::Instance(instance));
+        LoxFunction::new(
+            Rc::clone(&self.declaration),
+            Rc::new(RefCell::new(environment)),
+            self.is_initializer,
+        )
+    }
+}
+
+impl<'src> Callable<'src> for LoxFunction<'src> {
+    fn arity(&self) -> usize {
+        self.declaration.params.len()
+    }
+
+    fn call(
+        &self,
+        interpreter: &mut Interpreter<'src>,
+        arguments: Vec<Value<'src>>,
+    ) -> Result<Value<'src>, RuntimeError> {
+        let mut environment = Environment::with_enclosing(Rc::clone(&self.closure));
+        for (param, argument) in self.declaration.params.iter().zip(arguments) {
+            environment.define(param.name().to_string(), argument);
+        }
+
+        let result =
+            interpreter.execute_block(&self.declaration.body, Rc::new(RefCell::new(environment)));
+        let value = match result {
+            Ok(()) => Value::Nil,
+            Err(Interrupt::Return(value)) => value,
+            Err(Interrupt::Error(err)) => return Err(err),
+        };
+
+        if self.is_initializer {
+            return Ok(self
+                .closure
+                .borrow()
+                .get_at(0, "this")
+                .unwrap_or(Value::Nil));
+        }
+        Ok(value)
+    }
+}
+
+// the closure is left out on purpose: it may contain the function itself
+impl<'src> fmt::Debug for LoxFunction<'src> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LoxFunction<'src>")
+            .field("name", &self.declaration.name.name())
+            .finish()
+    }
+}
+
+impl<'src> fmt::Display for LoxFunction<'src> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<fn {}>", self.declaration.name.name())
+    }
+}
@@ -0,0 +1,63 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::interpreter::class::LoxClass;
+use crate::interpreter::error::RuntimeError;
+use crate::interpreter::error::RuntimeErrorKind;
+use crate::interpreter::value::Value;
+use crate::scanner::token::Token;
+
+pub struct LoxInstance<'src> {
+    pub class: Rc<LoxClass<'src>>,
+    fields: RefCell<HashMap<String, Value<'src>>>,
+}
+
+impl<'src> LoxInstance<'src> {
+    pub fn new(class: Rc<LoxClass<'src>>) -> LoxInstance<'src> {
+        LoxInstance {
+            class,
+            fields: RefCell::new(HashMap::new()),
+        }
+    }
+
+    // fields shadow methods of the same name; methods are bound to the instance
+    pub fn get(
+        instance: &Rc<LoxInstance<'src>>,
+        name: &Token,
+    ) -> Result<Value<'src>, RuntimeError> {
+        if let Some(value) = instance.fields.borrow().get(name.name()) {
+            return Ok(value.clone());
+        }
+        match instance.class.find_method(name.name()) {
+            Some(method) => Ok(Value::Callable(Rc::new(method.bind(Rc::clone(instance))))),
+            None => Err(RuntimeError::new(
+                RuntimeErrorKind::UndefinedProperty,
+                name.span,
+                format!("Undefined property '{}'.", name.name()),
+            )),
+        }
+    }
+
+    pub fn set(&self, name: &Token, value: Value<'src>) {
+        self.fields
+            .borrow_mut()
+            .insert(name.name().to_string(), value);
+    }
+}
+
+// the fields are left out on purpose: they may refer back to the instance
+impl<'src> fmt::Debug for LoxInstance<'src> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LoxInstance<'src>")
+            .field("class", &self.class.name)
+            .finish()
+    }
+}
+
+impl<'src> fmt::Display for LoxInstance<'src> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} instance", self.class.name)
+    }
+}
@@ -0,0 +1,824 @@
+pub mod callable;
+pub mod class;
+pub mod environment;
+pub mod error;
+pub mod function;
+pub mod instance;
+pub mod native;
+pub mod value;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::interpreter::callable::Callable;
+use crate::interpreter::class::LoxClass;
+use crate::interpreter::environment::undefined_variable;
+use crate::interpreter::environment::Environment;
+use crate::interpreter::error::RuntimeError;
+use crate::interpreter::error::RuntimeErrorKind;
+use crate::interpreter::function::LoxFunction;
+use crate::interpreter::instance::LoxInstance;
+use crate::interpreter::value::Value;
+use crate::scanner::token::Literal;
+use crate::scanner::token::Token;
+use crate::scanner::token_type::TokenType;
+use crate::syntax_tree::expression::Depth;
+use crate::syntax_tree::expression::Expression;
+use crate::syntax_tree::statement::FunctionDecl;
+use crate::syntax_tree::statement::Stmt;
+
+// how deeply calls may nest before the interpreter reports a stack overflow
+pub const MAX_CALL_DEPTH: usize = 1000;
+// a call that finds less native stack left than this runs on a new segment of
+// `STACK_SEGMENT` bytes, so deep recursion never overflows the thread's own stack
+// whatever its size. Unoptimized builds use tens of kilobytes per call.
+const RED_ZONE: usize = 1024 * 1024;
+const STACK_SEGMENT: usize = 8 * 1024 * 1024;
+
+#[derive(Debug)]
+pub struct Interpreter<'src> {
+    // the outermost scope, holding unresolved (global) variables and the native functions
+    globals: Rc<RefCell<Environment<'src>>>,
+    // the innermost scope of the code currently being executed
+    environment: Rc<RefCell<Environment<'src>>>,
+    // how many calls are currently being executed
+    call_depth: usize,
+}
+
+// why the execution of statements stopped before reaching their end
+#[derive(Debug)]
+pub enum Interrupt<'src> {
+    // a `return` statement unwinding to the enclosing call
+    Return(Value<'src>),
+    Error(RuntimeError),
+}
+
+impl<'src> From<RuntimeError> for Interrupt<'src> {
+    fn from(err: RuntimeError) -> Interrupt<'src> {
+        Interrupt::Error(err)
+    }
+}
+
+impl<'src> Default for Interpreter<'src> {
+    fn default() -> Interpreter<'src> {
+        Interpreter::new()
+    }
+}
+
+impl<'src> Interpreter<'src> {
+    pub fn new() -> Interpreter<'src> {
+        let globals = Rc::new(RefCell::new(Environment::new()));
+        for native in native::globals() {
+            globals
+                .borrow_mut()
+                .define(native.name.to_string(), Value::Callable(Rc::new(native)));
+        }
+        Interpreter {
+            environment: Rc::clone(&globals),
+            globals,
+            call_depth: 0,
+        }
+    }
+
+    pub fn interpret(&mut self, statements: &[Stmt<'src>]) -> Result<(), RuntimeError> {
+        for stmt in statements {
+            match self.execute(stmt) {
+                // a top-level `return` simply ends the program
+                Err(Interrupt::Return(_)) => return Ok(()),
+                Err(Interrupt::Error(err)) => return Err(err),
+                Ok(()) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, stmt: &Stmt<'src>) -> Result<(), Interrupt<'src>> {
+        match stmt {
+            Stmt::Expression(expr) => {
+                self.evaluate(expr)?;
+            }
+            Stmt::Function(declaration) => {
+                let function =
+                    LoxFunction::new(Rc::clone(declaration), Rc::clone(&self.environment), false);
+                self.environment.borrow_mut().define(
+                    declaration.name.name().to_string(),
+                    Value::Callable(Rc::new(function)),
+                );
+            }
+            Stmt::Return { value, .. } => {
+                let value = match value {
+                    Some(expr) => self.evaluate(expr)?,
+                    None => Value::Nil,
+                };
+                return Err(Interrupt::Return(value));
+            }
+            Stmt::Print(expr) => {
+                let value = self.evaluate(expr)?;
+                println!("{}", value);
+            }
+            Stmt::Var { name, initializer } => {
+                let value = match initializer {
+                    Some(expr) => self.evaluate(expr)?,
+                    None => Value::Nil,
+                };
+                self.environment
+                    .borrow_mut()
+                    .define(name.name().to_string(), value);
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                if self.evaluate(condition)?.is_truthy() {
+                    self.execute(then_branch)?;
+                } else if let Some(else_branch) = else_branch {
+                    self.execute(else_branch)?;
+                }
+            }
+            Stmt::While { condition, body } => {
+                while self.evaluate(condition)?.is_truthy() {
+                    self.execute(body)?;
+                }
+            }
+            Stmt::Class {
+                name,
+                superclass,
+                methods,
+            } => self.class_declaration(name, superclass.as_ref(), methods)?,
+            Stmt::Block(statements) => {
+                let environment = Environment::with_enclosing(Rc::clone(&self.environment));
+                self.execute_block(statements, Rc::new(RefCell::new(environment)))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn class_declaration(
+        &mut self,
+        name: &Token,
+        superclass: Option<&Expression<'src>>,
+        methods: &[Rc<FunctionDecl<'src>>],
+    ) -> Result<(), RuntimeError> {
+        let superclass = match superclass {
+            Some(expr) => match self.evaluate(expr)? {
+                Value::Class(class) => Some(class),
+                _ => {
+                    return Err(RuntimeError::new(
+                        RuntimeErrorKind::InvalidSuperclass,
+                        expr.span(),
+                        String::from("Superclass must be a class."),
+                    ))
+                }
+            },
+            None => None,
+        };
+
+        self.environment
+            .borrow_mut()
+            .define(name.name().to_string(), Value::Nil);
+
+        // methods of a subclass close over a scope that binds `super`
+        let method_scope = match &superclass {
+            Some(superclass) => {
+                let mut environment = Environment::with_enclosing(Rc::clone(&self.environment));
+                environment.define(String::from("super"), Value::Class(Rc::clone(superclass)));
+                Rc::new(RefCell::new(environment))
+            }
+            None => Rc::clone(&self.environment),
+        };
+
+        let mut class_methods: HashMap<String, Rc<LoxFunction<'src>>> = HashMap::new();
+        for method in methods {
+            let is_initializer = method.name.name() == "init";
+            let function =
+                LoxFunction::new(Rc::clone(method), Rc::clone(&method_scope), is_initializer);
+            class_methods.insert(method.name.name().to_string(), Rc::new(function));
+        }
+
+        let class = LoxClass::new(name.name().to_string(), superclass, class_methods);
+        self.environment
+            .borrow_mut()
+            .assign(name, Value::Class(Rc::new(class)))
+    }
+
+    // runs the statements in the given scope, restoring the current one afterwards
+    // even if one of them fails
+    pub(crate) fn execute_block(
+        &mut self,
+        statements: &[Stmt<'src>],
+        environment: Rc<RefCell<Environment<'src>>>,
+    ) -> Result<(), Interrupt<'src>> {
+        let previous = std::mem::replace(&mut self.environment, environment);
+        let result = statements.iter().try_for_each(|stmt| self.execute(stmt));
+        self.environment = previous;
+        result
+    }
+
+    pub fn evaluate(&mut self, expr: &Expression<'src>) -> Result<Value<'src>, RuntimeError> {
+        match expr {
+            Expression::Literal(token) => Ok(literal_value(token)),
+            Expression::Grouping { expression, .. } => self.evaluate(expression),
+            Expression::Call {
+                callee,
+                paren,
+                arguments,
+                ..
+            } => {
+                let callee = self.evaluate(callee)?;
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| self.evaluate(argument))
+                    .collect::<Result<Vec<Value<'src>>, RuntimeError>>()?;
+                self.call(callee, paren, arguments)
+            }
+            Expression::Get { object, name, .. } => match self.evaluate(object)? {
+                Value::Instance(instance) => LoxInstance::get(&instance, name),
+                _ => Err(RuntimeError::new(
+                    RuntimeErrorKind::NotAnInstance,
+                    name.span,
+                    String::from("Only instances have properties."),
+                )),
+            },
+            Expression::Set {
+                object,
+                name,
+                value,
+                ..
+            } => {
+                let instance = match self.evaluate(object)? {
+                    Value::Instance(instance) => instance,
+                    _ => {
+                        return Err(RuntimeError::new(
+                            RuntimeErrorKind::NotAnInstance,
+                            name.span,
+                            String::from("Only instances have fields."),
+                        ))
+                    }
+                };
+                let value = self.evaluate(value)?;
+                instance.set(name, value.clone());
+                Ok(value)
+            }
+            Expression::Super {
+                keyword,
+                method,
+                depth,
+                ..
+            } => self.super_method(keyword, method, depth),
+            Expression::This { keyword, depth } => self.this(keyword, depth.get()),
+            Expression::Variable { name, depth } => match depth.get() {
+                Some(distance) => self
+                    .environment
+                    .borrow()
+                    .get_at(distance, name.name())
+                    .ok_or_else(|| undefined_variable(name)),
+                None => self.globals.borrow().get(name),
+            },
+            Expression::Assign {
+                name, value, depth, ..
+            } => {
+                let value = self.evaluate(value)?;
+                match depth.get() {
+                    Some(distance) => {
+                        self.environment
+                            .borrow_mut()
+                            .assign_at(distance, name, value.clone())?
+                    }
+                    None => self.globals.borrow_mut().assign(name, value.clone())?,
+                }
+                Ok(value)
+            }
+            Expression::Unary {
+                operator, right, ..
+            } => {
+                let right = self.evaluate(right)?;
+                match operator.token_type {
+                    TokenType::Minus => match right {
+                        Value::Number(n) => Ok(Value::Number(-n)),
+                        _ => Err(type_error(operator, "Operand must be a number.")),
+                    },
+                    TokenType::Bang => Ok(Value::Bool(!right.is_truthy())),
+                    _ => Err(type_error(operator, "Unknown unary operator.")),
+                }
+            }
+            Expression::Logical {
+                left,
+                operator,
+                right,
+                ..
+            } => {
+                // short-circuit, returning the operand that decided the outcome
+                let left = self.evaluate(left)?;
+                let decided = match operator.token_type {
+                    TokenType::Or => left.is_truthy(),
+                    _ => !left.is_truthy(),
+                };
+                if decided {
+                    return Ok(left);
+                }
+                self.evaluate(right)
+            }
+            Expression::Binary {
+                left,
+                operator,
+                right,
+                ..
+            } => {
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
+                binary(operator, left, right)
+            }
+        }
+    }
+
+    // every call recurses on the host's stack, so runaway recursion is stopped with
+    // an error before it can overflow it
+    fn call(
+        &mut self,
+        callee: Value<'src>,
+        paren: &Token,
+        arguments: Vec<Value<'src>>,
+    ) -> Result<Value<'src>, RuntimeError> {
+        if self.call_depth >= MAX_CALL_DEPTH {
+            return Err(RuntimeError::new(
+                RuntimeErrorKind::StackOverflow,
+                paren.span,
+                String::from("Stack overflow."),
+            ));
+        }
+        self.call_depth += 1;
+        let result = stacker::maybe_grow(RED_ZONE, STACK_SEGMENT, || {
+            self.call_value(callee, paren, arguments)
+        });
+        self.call_depth -= 1;
+        result
+    }
+
+    fn call_value(
+        &mut self,
+        callee: Value<'src>,
+        paren: &Token,
+        arguments: Vec<Value<'src>>,
+    ) -> Result<Value<'src>, RuntimeError> {
+        let function: Rc<dyn Callable<'src> + 'src> = match callee {
+            Value::Callable(function) => function,
+            Value::Class(class) => {
+                if arguments.len() != class.arity() {
+                    return Err(arity_mismatch(paren, class.arity(), arguments.len()));
+                }
+                return LoxClass::instantiate(&class, self, arguments);
+            }
+            _ => {
+                return Err(RuntimeError::new(
+                    RuntimeErrorKind::NotCallable,
+                    paren.span,
+                    String::from("Can only call functions and classes."),
+                ))
+            }
+        };
+        if arguments.len() != function.arity() {
+            return Err(arity_mismatch(paren, function.arity(), arguments.len()));
+        }
+        function.call(self, arguments)
+    }
+
+    fn this(&self, keyword: &Token, depth: Option<usize>) -> Result<Value<'src>, RuntimeError> {
+        depth
+            .and_then(|distance| self.environment.borrow().get_at(distance, "this"))
+            .ok_or_else(|| {
+                RuntimeError::new(
+                    RuntimeErrorKind::UndefinedVariable,
+                    keyword.span,
+                    String::from("Can't use 'this' outside of a class."),
+                )
+            })
+    }
+
+    // looks the method up starting at the superclass of the class containing the
+    // method being executed, and binds it to the current `this`
+    fn super_method(
+        &self,
+        keyword: &Token,
+        method: &Token,
+        depth: &Depth,
+    ) -> Result<Value<'src>, RuntimeError> {
+        let distance = depth.get();
+        let superclass = match distance.and_then(|d| self.environment.borrow().get_at(d, "super")) {
+            Some(Value::Class(superclass)) => superclass,
+            _ => {
+                return Err(RuntimeError::new(
+                    RuntimeErrorKind::UndefinedVariable,
+                    keyword.span,
+                    String::from("Can't use 'super' in a class with no superclass."),
+                ))
+            }
+        };
+        // `this` is always bound in the scope just inside the one binding `super`
+        let instance = match self.this(keyword, distance.map(|d| d - 1))? {
+            Value::Instance(instance) => instance,
+            _ => unreachable!("'this' is always bound to an instance"),
+        };
+
+        match superclass.find_method(method.name()) {
+            Some(found) => Ok(Value::Callable(Rc::new(found.bind(instance)))),
+            None => Err(RuntimeError::new(
+                RuntimeErrorKind::UndefinedProperty,
+                method.span,
+                format!("Undefined property '{}'.", method.name()),
+            )),
+        }
+    }
+}
+
+fn binary<'src>(
+    operator: &Token,
+    left: Value<'src>,
+    right: Value<'src>,
+) -> Result<Value<'src>, RuntimeError> {
+    match operator.token_type {
+        TokenType::EqualEqual => return Ok(Value::Bool(left == right)),
+        TokenType::BangEqual => return Ok(Value::Bool(left != right)),
+        TokenType::Plus => {
+            return match (left, right) {
+                (Value::Number(l), Value::Number(r)) => Ok(Value::Number(l + r)),
+                (Value::String(l), Value::String(r)) => Ok(Value::String(l + &r)),
+                _ => Err(type_error(
+                    operator,
+                    "Operands must be two numbers or two strings.",
+                )),
+            }
+        }
+        _ => {}
+    }
+
+    let (l, r) = match (left, right) {
+        (Value::Number(l), Value::Number(r)) => (l, r),
+        _ => return Err(type_error(operator, "Operands must be numbers.")),
+    };
+    match operator.token_type {
+        TokenType::Minus => Ok(Value::Number(l - r)),
+        TokenType::Star => Ok(Value::Number(l * r)),
+        TokenType::Slash => Ok(Value::Number(l / r)),
+        TokenType::Greater => Ok(Value::Bool(l > r)),
+        TokenType::GreaterEqual => Ok(Value::Bool(l >= r)),
+        TokenType::Less => Ok(Value::Bool(l < r)),
+        TokenType::LessEqual => Ok(Value::Bool(l <= r)),
+        _ => Err(type_error(operator, "Unknown binary operator.")),
+    }
+}
+
+fn arity_mismatch(paren: &Token, expected: usize, got: usize) -> RuntimeError {
+    RuntimeError::new(
+        RuntimeErrorKind::ArityMismatch,
+        paren.span,
+        format!("Expected {} arguments but got {}.", expected, got),
+    )
+}
+
+fn literal_value<'src>(token: &Token<'src>) -> Value<'src> {
+    match (&token.token_type, &token.literal) {
+        (TokenType::True, _) => Value::Bool(true),
+        (TokenType::False, _) => Value::Bool(false),
+        (_, Some(Literal::Numeric(n))) => Value::Number(*n),
+        (_, Some(Literal::String(s))) => Value::String(s.to_string()),
+        _ => Value::Nil,
+    }
+}
+
+fn type_error(operator: &Token, message: &str) -> RuntimeError {
+    RuntimeError::new(
+        RuntimeErrorKind::TypeError,
+        operator.span,
+        String::from(message),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolver::resolve;
+    use crate::scanner::lexer::scan_tokens;
+    use crate::scanner::span::Span;
+    use crate::syntax_tree::parser::Parser;
+
+    fn evaluate_source(source: &str) -> Result<Value<'_>, RuntimeError> {
+        let tokens = scan_tokens(source).unwrap();
+        let expr = Parser::new(tokens).expression().unwrap();
+        Interpreter::new().evaluate(&expr)
+    }
+
+    #[test]
+    fn test_evaluate_arithmetic() {
+        assert_eq!(evaluate_source("1 + 2 * 3"), Ok(Value::Number(7.0)));
+        assert_eq!(evaluate_source("(1 + 2) * 3"), Ok(Value::Number(9.0)));
+        assert_eq!(evaluate_source("-(4 / 8)"), Ok(Value::Number(-0.5)));
+    }
+
+    #[test]
+    fn test_evaluate_string_concatenation() {
+        assert_eq!(
+            evaluate_source("\"foo\" + \"bar\""),
+            Ok(Value::String(String::from("foobar")))
+        );
+    }
+
+    #[test]
+    fn test_evaluate_truthiness_and_equality() {
+        assert_eq!(evaluate_source("!nil"), Ok(Value::Bool(true)));
+        assert_eq!(evaluate_source("!0"), Ok(Value::Bool(false)));
+        assert_eq!(evaluate_source("nil == nil"), Ok(Value::Bool(true)));
+        assert_eq!(evaluate_source("1 == \"1\""), Ok(Value::Bool(false)));
+        assert_eq!(evaluate_source("2 >= 2"), Ok(Value::Bool(true)));
+    }
+
+    #[test]
+    fn test_evaluate_type_error_points_at_operator() {
+        assert_eq!(
+            evaluate_source("1 + \"a\""),
+            Err(RuntimeError::new(
+                RuntimeErrorKind::TypeError,
+                Span::new(2, 3, 1, 3),
+                String::from("Operands must be two numbers or two strings."),
+            ))
+        );
+        assert_eq!(
+            evaluate_source("-true").unwrap_err().message,
+            "Operand must be a number."
+        );
+    }
+
+    fn run_source<'src>(
+        interpreter: &mut Interpreter<'src>,
+        source: &'src str,
+    ) -> Result<(), RuntimeError> {
+        let tokens = scan_tokens(source).unwrap();
+        let (statements, errors) = Parser::new(tokens).parse();
+        assert!(errors.is_empty(), "unexpected parse errors: {:?}", errors);
+        let errors = resolve(&statements);
+        assert!(errors.is_empty(), "unexpected resolve errors: {:?}", errors);
+        interpreter.interpret(&statements)
+    }
+
+    fn global<'src>(interpreter: &Interpreter<'src>, name: &str) -> Value<'src> {
+        let tokens = scan_tokens(name).unwrap();
+        interpreter.environment.borrow().get(&tokens[0]).unwrap()
+    }
+
+    #[test]
+    fn test_block_scopes_shadow_and_assign() {
+        let mut interpreter = Interpreter::new();
+        run_source(
+            &mut interpreter,
+            "var a = 1; var b = 2; { var a = 10; b = a + b; } var c = a = 3;",
+        )
+        .unwrap();
+
+        assert_eq!(global(&interpreter, "a"), Value::Number(3.0));
+        assert_eq!(global(&interpreter, "b"), Value::Number(12.0));
+        assert_eq!(global(&interpreter, "c"), Value::Number(3.0));
+    }
+
+    #[test]
+    fn test_unicode_identifiers_are_normalized() {
+        let mut interpreter = Interpreter::new();
+        // the first spelling uses a precomposed é, the second e and a combining accent
+        run_source(
+            &mut interpreter,
+            "var caf\u{e9} = 1; caf\u{65}\u{301} = caf\u{65}\u{301} + 1; var x1 = 3;",
+        )
+        .unwrap();
+
+        assert_eq!(global(&interpreter, "caf\u{e9}"), Value::Number(2.0));
+        assert_eq!(global(&interpreter, "x1"), Value::Number(3.0));
+    }
+
+    #[test]
+    fn test_assign_undefined_variable() {
+        let mut interpreter = Interpreter::new();
+        let err = run_source(&mut interpreter, "{ x = 1; }").unwrap_err();
+
+        assert_eq!(err.kind, RuntimeErrorKind::UndefinedVariable);
+        assert_eq!(err.message, "Undefined variable 'x'.");
+    }
+
+    #[test]
+    fn test_block_scope_restored_after_error() {
+        let mut interpreter = Interpreter::new();
+        run_source(&mut interpreter, "var a = 1;").unwrap();
+        assert!(run_source(&mut interpreter, "{ var a = 2; -nil; }").is_err());
+
+        assert_eq!(global(&interpreter, "a"), Value::Number(1.0));
+    }
+
+    #[test]
+    fn test_logical_operators_return_deciding_operand() {
+        assert_eq!(
+            evaluate_source("nil or \"yes\""),
+            Ok(Value::String(String::from("yes")))
+        );
+        assert_eq!(evaluate_source("1 or 2"), Ok(Value::Number(1.0)));
+        assert_eq!(evaluate_source("nil and 2"), Ok(Value::Nil));
+        assert_eq!(evaluate_source("1 and 2"), Ok(Value::Number(2.0)));
+        // the right operand is never evaluated when the left one decides
+        assert_eq!(evaluate_source("true or -nil"), Ok(Value::Bool(true)));
+    }
+
+    #[test]
+    fn test_control_flow() {
+        let mut interpreter = Interpreter::new();
+        run_source(
+            &mut interpreter,
+            "var sum = 0; for (var i = 1; i <= 4; i = i + 1) { if (i == 3) sum = sum + 100; else sum = sum + i; } \
+             var n = 0; while (n < 5) n = n + 2;",
+        )
+        .unwrap();
+
+        assert_eq!(global(&interpreter, "sum"), Value::Number(107.0));
+        assert_eq!(global(&interpreter, "n"), Value::Number(6.0));
+    }
+
+    #[test]
+    fn test_functions_and_recursion() {
+        let mut interpreter = Interpreter::new();
+        run_source(
+            &mut interpreter,
+            "fun fib(n) { if (n < 2) return n; return fib(n - 1) + fib(n - 2); } \
+             var result = fib(10); \
+             fun nothing() {} var empty = nothing();",
+        )
+        .unwrap();
+
+        assert_eq!(global(&interpreter, "result"), Value::Number(55.0));
+        assert_eq!(global(&interpreter, "empty"), Value::Nil);
+    }
+
+    #[test]
+    fn test_closures_capture_their_environment() {
+        let mut interpreter = Interpreter::new();
+        run_source(
+            &mut interpreter,
+            "fun makeCounter() { var i = 0; fun count() { i = i + 1; return i; } return count; } \
+             var counter = makeCounter(); counter(); var second = counter(); \
+             var other = makeCounter()();",
+        )
+        .unwrap();
+
+        assert_eq!(global(&interpreter, "second"), Value::Number(2.0));
+        assert_eq!(global(&interpreter, "other"), Value::Number(1.0));
+    }
+
+    #[test]
+    fn test_call_errors() {
+        let mut interpreter = Interpreter::new();
+
+        let err = run_source(&mut interpreter, "fun f(a) {} f(1, 2);").unwrap_err();
+        assert_eq!(err.kind, RuntimeErrorKind::ArityMismatch);
+        assert_eq!(err.message, "Expected 1 arguments but got 2.");
+        assert_eq!(err.span, Span::new(18, 19, 1, 19));
+
+        let err = run_source(&mut interpreter, "\"not a function\"();").unwrap_err();
+        assert_eq!(err.kind, RuntimeErrorKind::NotCallable);
+    }
+
+    #[test]
+    fn test_native_clock() {
+        let mut interpreter = Interpreter::new();
+        run_source(&mut interpreter, "var now = clock();").unwrap();
+
+        match global(&interpreter, "now") {
+            Value::Number(n) => assert!(n > 0.0),
+            value => panic!("expected a number, got {:?}", value),
+        }
+        assert_eq!(global(&interpreter, "clock").to_string(), "<native fn>");
+    }
+
+    #[test]
+    fn test_classes_fields_and_methods() {
+        let mut interpreter = Interpreter::new();
+        run_source(
+            &mut interpreter,
+            "class Point { init(x, y) { this.x = x; this.y = y; } \
+               sum() { return this.x + this.y; } } \
+             var p = Point(1, 2); p.y = 10; var sum = p.sum(); \
+             var method = p.sum; p.x = 5; var bound = method(); \
+             var same = p.init(0, 0) == p;",
+        )
+        .unwrap();
+
+        assert_eq!(global(&interpreter, "sum"), Value::Number(11.0));
+        assert_eq!(global(&interpreter, "bound"), Value::Number(15.0));
+        assert_eq!(global(&interpreter, "same"), Value::Bool(true));
+        assert_eq!(global(&interpreter, "p").to_string(), "Point instance");
+        assert_eq!(global(&interpreter, "Point").to_string(), "Point");
+    }
+
+    #[test]
+    fn test_initializer_early_return_yields_instance() {
+        let mut interpreter = Interpreter::new();
+        run_source(
+            &mut interpreter,
+            "class A { init() { this.a = 1; return; this.a = 2; } } var a = A().a;",
+        )
+        .unwrap();
+
+        assert_eq!(global(&interpreter, "a"), Value::Number(1.0));
+    }
+
+    #[test]
+    fn test_inheritance_and_super() {
+        let mut interpreter = Interpreter::new();
+        run_source(
+            &mut interpreter,
+            "class A { name() { return \"A\"; } greet() { return \"hi \" + this.name(); } } \
+             class B < A { name() { return \"B\" + super.name(); } } \
+             class C < B {} \
+             var greeting = C().greet();",
+        )
+        .unwrap();
+
+        assert_eq!(
+            global(&interpreter, "greeting"),
+            Value::String(String::from("hi BA"))
+        );
+    }
+
+    #[test]
+    fn test_class_errors() {
+        let mut interpreter = Interpreter::new();
+
+        let err = run_source(&mut interpreter, "var x = 1; class A < x {}").unwrap_err();
+        assert_eq!(err.kind, RuntimeErrorKind::InvalidSuperclass);
+
+        let err = run_source(&mut interpreter, "class B {} B().missing;").unwrap_err();
+        assert_eq!(err.kind, RuntimeErrorKind::UndefinedProperty);
+        assert_eq!(err.message, "Undefined property 'missing'.");
+
+        let err = run_source(&mut interpreter, "x.y = 2;").unwrap_err();
+        assert_eq!(err.kind, RuntimeErrorKind::NotAnInstance);
+
+        let err = run_source(&mut interpreter, "class C { init(a) {} } C();").unwrap_err();
+        assert_eq!(err.kind, RuntimeErrorKind::ArityMismatch);
+    }
+
+    #[test]
+    fn test_closures_bind_statically() {
+        let mut interpreter = Interpreter::new();
+        run_source(
+            &mut interpreter,
+            "var a = \"global\"; var first; var second; \
+             { fun show() { return a; } first = show(); var a = \"block\"; second = show(); }",
+        )
+        .unwrap();
+
+        assert_eq!(
+            global(&interpreter, "first"),
+            Value::String(String::from("global"))
+        );
+        assert_eq!(
+            global(&interpreter, "second"),
+            Value::String(String::from("global"))
+        );
+    }
+
+    #[test]
+    fn test_value_display() {
+        assert_eq!(Value::Number(3.0).to_string(), "3");
+        assert_eq!(Value::Number(2.5).to_string(), "2.5");
+        assert_eq!(Value::Nil.to_string(), "nil");
+    }
+
+    #[test]
+    fn test_unbounded_recursion_is_a_runtime_error() {
+        let mut interpreter = Interpreter::new();
+
+        let err = run_source(&mut interpreter, "fun f() { f(); } f();").unwrap_err();
+
+        assert_eq!(err.kind, RuntimeErrorKind::StackOverflow);
+        assert_eq!(err.message, "Stack overflow.");
+
+        // the depth is back to zero once the error has unwound
+        run_source(
+            &mut interpreter,
+            "fun g(n) { if (n > 1) g(n - 1); } g(1000);",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_unbounded_recursion_on_a_default_thread() {
+        // embedders run the interpreter on whatever thread they have
+        let test = std::thread::spawn(|| {
+            let source = "fun f(n) { return f(n + 1); } f(0);";
+            let (statements, errors) = Parser::new(scan_tokens(source).unwrap()).parse();
+            assert!(errors.is_empty());
+            assert!(resolve(&statements).is_empty());
+
+            let err = Interpreter::new().interpret(&statements).unwrap_err();
+
+            assert_eq!(err.kind, RuntimeErrorKind::StackOverflow);
+        });
+        test.join().unwrap();
+    }
+}
@@ -0,0 +1,54 @@
+use std::fmt;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use crate::interpreter::callable::Callable;
+use crate::interpreter::error::RuntimeError;
+use crate::interpreter::value::Value;
+use crate::interpreter::Interpreter;
+
+// a function implemented in Rust and exposed to Lox code
+#[derive(Debug)]
+pub struct NativeFunction<'src> {
+    pub name: &'static str,
+    pub arity: usize,
+    pub function: fn(&[Value<'src>]) -> Value<'src>,
+}
+
+impl<'src> Callable<'src> for NativeFunction<'src> {
+    fn arity(&self) -> usize {
+        self.arity
+    }
+
+    fn call(
+        &self,
+        _: &mut Interpreter<'src>,
+        arguments: Vec<Value<'src>>,
+    ) -> Result<Value<'src>, RuntimeError> {
+        Ok((self.function)(&arguments))
+    }
+}
+
+impl<'src> fmt::Display for NativeFunction<'src> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+// the native functions available in the global scope of every program
+pub fn globals<'src>() -> Vec<NativeFunction<'src>> {
+    vec![NativeFunction {
+        name: "clock",
+        arity: 0,
+        function: clock,
+    }]
+}
+
+// seconds since the unix epoch, useful for benchmarking
+fn clock<'src>(_: &[Value<'src>]) -> Value<'src> {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0);
+    Value::Number(elapsed)
+}
@@ -0,0 +1,55 @@
+use std::fmt;
+use std::rc::Rc;
+
+use crate::interpreter::callable::Callable;
+use crate::interpreter::class::LoxClass;
+use crate::interpreter::instance::LoxInstance;
+
+#[derive(Debug, Clone)]
+pub enum Value<'src> {
+    Nil,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Callable(Rc<dyn Callable<'src> + 'src>),
+    Class(Rc<LoxClass<'src>>),
+    Instance(Rc<LoxInstance<'src>>),
+}
+
+impl<'src> Value<'src> {
+    // `false` and `nil` are falsey, everything else is truthy
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Value::Nil | Value::Bool(false))
+    }
+}
+
+impl<'src> PartialEq for Value<'src> {
+    fn eq(&self, other: &Value<'src>) -> bool {
+        match (self, other) {
+            (Value::Nil, Value::Nil) => true,
+            (Value::Bool(l), Value::Bool(r)) => l == r,
+            (Value::Number(l), Value::Number(r)) => l == r,
+            (Value::String(l), Value::String(r)) => l == r,
+            // functions, classes and instances are only equal to themselves
+            (Value::Callable(l), Value::Callable(r)) => Rc::ptr_eq(l, r),
+            (Value::Class(l), Value::Class(r)) => Rc::ptr_eq(l, r),
+            (Value::Instance(l), Value::Instance(r)) => Rc::ptr_eq(l, r),
+            _ => false,
+        }
+    }
+}
+
+impl<'src> fmt::Display for Value<'src> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Nil => write!(f, "nil"),
+            Value::Bool(b) => write!(f, "{}", b),
+            // `{}` already omits the fractional part of integral floats
+            Value::Number(n) => write!(f, "{}", n),
+            Value::String(s) => write!(f, "{}", s),
+            Value::Callable(c) => write!(f, "{}", c),
+            Value::Class(c) => write!(f, "{}", c),
+            Value::Instance(i) => write!(f, "{}", i),
+        }
+    }
+}
@@ -0,0 +1,7 @@
+pub mod diagnostic;
+pub mod error;
+pub mod explanations;
+pub mod interpreter;
+pub mod resolver;
+pub mod scanner;
+pub mod syntax_tree;
@@ -1,30 +1,145 @@
-use crate::scanner::lexer::scan_tokens;
-use crate::syntax_tree::expression::Parser;
+use lox_rust::diagnostic::Renderer;
+use lox_rust::error::LoxError;
+use lox_rust::error::LoxErrorKind;
+use lox_rust::explanations::explain;
+use lox_rust::interpreter::Interpreter;
+use lox_rust::resolver::resolve;
+use lox_rust::resolver::resolve_expression;
+use lox_rust::scanner::error::LexError;
+use lox_rust::scanner::lexer::scan_tokens_lossless;
+use lox_rust::scanner::lexer::Lexer;
+use lox_rust::scanner::span::Span;
+use lox_rust::scanner::token::Token;
+use lox_rust::syntax_tree::formatter::Formatter;
+use lox_rust::syntax_tree::parser::Parser;
+use lox_rust::syntax_tree::printer::AstPrinter;
+use lox_rust::syntax_tree::printer::RpnPrinter;
+use lox_rust::syntax_tree::visitor::Visitor;
 use std::env;
 use std::fs;
 use std::io;
+use std::io::IsTerminal;
 use std::io::Write;
+use typed_arena::Arena;
 
-mod scanner;
-mod syntax_tree;
+const USAGE: &str =
+    "Usage: jlox [--ast=lisp|rpn] [--color=auto|always|never] [--error-format=human|json] [script]
+       jlox fmt [--check|--write] [--color=auto|always|never] [--error-format=human|json] script...
+       jlox --explain CODE";
+
+// what to do with the parsed source
+#[derive(Debug, Clone, Copy)]
+enum Mode {
+    Run,
+    // print the syntax tree instead of running it
+    PrintAst(AstFormat),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum AstFormat {
+    Lisp,
+    Rpn,
+}
+
+// how errors are reported
+#[derive(Debug, Clone, Copy)]
+enum ErrorFormat {
+    // rendered with source snippets to stdout
+    Human { color: bool },
+    // one JSON object per line to stderr, kept apart from the program's output
+    Json,
+}
+
+impl ErrorFormat {
+    fn new(color: bool, json: bool) -> ErrorFormat {
+        if json {
+            ErrorFormat::Json
+        } else {
+            ErrorFormat::Human { color }
+        }
+    }
+}
+
+// what `fmt` does with the formatted source
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FmtMode {
+    Print,
+    // exit with an error if any script isn't formatted, without changing it
+    Check,
+    // rewrite the scripts in place
+    Write,
+}
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().is_some_and(|arg| arg == "fmt") {
+        return fmt(&args[1..]);
+    }
+    if args.first().is_some_and(|arg| arg == "--explain") {
+        return match &args[1..] {
+            [code] => explain_code(code),
+            _ => usage(),
+        };
+    }
 
-    match args.len() {
-        1 => run_prompt(),
-        2 => run_file(args[1].to_string()),
-        _ => {
-            println!("Usage: jlox [script]");
-            std::process::exit(64)
+    let mut mode = Mode::Run;
+    let mut color = default_color();
+    let mut json = false;
+    let mut scripts: Vec<String> = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--ast=lisp" => mode = Mode::PrintAst(AstFormat::Lisp),
+            "--ast=rpn" => mode = Mode::PrintAst(AstFormat::Rpn),
+            flag if flag.starts_with("--color=") => color = color_flag(flag),
+            flag if flag.starts_with("--error-format=") => json = error_format_flag(flag),
+            flag if flag.starts_with("--") => usage(),
+            _ => scripts.push(arg),
         }
     }
+
+    let format = ErrorFormat::new(color, json);
+    match scripts.len() {
+        0 => run_prompt(mode, format),
+        1 => run_file(scripts.remove(0), mode, format),
+        _ => usage(),
+    }
+}
+
+// errors are coloured when printed to a terminal, unless NO_COLOR is set
+fn default_color() -> bool {
+    io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none()
+}
+
+fn color_flag(flag: &str) -> bool {
+    match flag.trim_start_matches("--color=") {
+        "auto" => default_color(),
+        "always" => true,
+        "never" => false,
+        _ => usage(),
+    }
+}
+
+// whether errors are reported as JSON
+fn error_format_flag(flag: &str) -> bool {
+    match flag.trim_start_matches("--error-format=") {
+        "human" => false,
+        "json" => true,
+        _ => usage(),
+    }
 }
 
-fn run_file(path: String) {
-    println!("File Mode. The path is {}", path);
+fn usage() -> ! {
+    println!("{}", USAGE);
+    std::process::exit(64)
+}
 
-    let contents = match fs::read_to_string(path) {
+fn run_file(path: String, mode: Mode, format: ErrorFormat) {
+    // stdout only carries the program's output when errors are read by a machine
+    if let ErrorFormat::Human { .. } = format {
+        println!("File Mode. The path is {}", path);
+    }
+
+    let contents = match fs::read_to_string(&path) {
         Ok(contents) => contents,
         Err(e) => {
             println!("Error reading file: {}", e);
@@ -32,15 +147,100 @@ fn run_file(path: String) {
         }
     };
 
-    run(contents)
+    let result = match mode {
+        Mode::Run => run(&mut Interpreter::new(), &contents, 1),
+        Mode::PrintAst(format) => print_ast(&contents, 1, format),
+    };
+    if let Err(errors) = result {
+        report(&errors, &path, &contents, 1, format);
+        // mirror sysexits: 70 for failures at runtime, 65 for malformed input
+        if errors
+            .iter()
+            .any(|err| matches!(err.kind, LoxErrorKind::Runtime(_)))
+        {
+            std::process::exit(70)
+        }
+        std::process::exit(65)
+    }
 }
 
-fn run_prompt() {
+fn fmt(args: &[String]) {
+    let mut mode = FmtMode::Print;
+    let mut color = default_color();
+    let mut json = false;
+    let mut scripts: Vec<&String> = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--check" => mode = FmtMode::Check,
+            "--write" => mode = FmtMode::Write,
+            flag if flag.starts_with("--color=") => color = color_flag(flag),
+            flag if flag.starts_with("--error-format=") => json = error_format_flag(flag),
+            flag if flag.starts_with("--") => usage(),
+            _ => scripts.push(arg),
+        }
+    }
+    if scripts.is_empty() {
+        usage()
+    }
+    let format = ErrorFormat::new(color, json);
+
+    let mut unformatted = false;
+    for path in scripts {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                println!("Error reading file: {}", e);
+                std::process::exit(1)
+            }
+        };
+        let formatted = match format_source(&contents) {
+            Ok(formatted) => formatted,
+            Err(errors) => {
+                report(&errors, path, &contents, 1, format);
+                std::process::exit(65)
+            }
+        };
+        match mode {
+            FmtMode::Print => print!("{}", formatted),
+            FmtMode::Check if formatted != contents => {
+                println!("{} is not formatted", path);
+                unformatted = true;
+            }
+            FmtMode::Write if formatted != contents => {
+                if let Err(e) = fs::write(path, formatted) {
+                    println!("Error writing file: {}", e);
+                    std::process::exit(1)
+                }
+            }
+            FmtMode::Check | FmtMode::Write => {}
+        }
+    }
+    if unformatted {
+        std::process::exit(1)
+    }
+}
+
+fn explain_code(code: &str) {
+    match explain(code) {
+        Some(explanation) => print!("{}", explanation),
+        None => {
+            println!("Unknown error code '{}'.", code);
+            std::process::exit(64)
+        }
+    }
+}
+
+fn run_prompt(mode: Mode, format: ErrorFormat) {
     println!("Prompt Mode.");
 
-    let mut line;
+    // functions and classes defined on a line outlive it, and they borrow their
+    // names and bodies from the source, so every line is kept until the prompt exits
+    let lines: Arena<String> = Arena::new();
+    // the lines read so far, numbered from 1 like the lines of a script
+    let mut sources: Vec<&str> = Vec::new();
+    let mut interpreter = Interpreter::new();
     loop {
-        line = String::from("");
+        let mut line = String::new();
 
         print!("> ");
         let res = io::stdout().flush();
@@ -59,23 +259,180 @@ fn run_prompt() {
             std::process::exit(0)
         }
 
-        run(line)
+        let line = lines.alloc(line).trim_end_matches(['\r', '\n']);
+        sources.push(line);
+        let number = sources.len();
+        let result = match mode {
+            Mode::Run => run_line(&mut interpreter, line, number),
+            Mode::PrintAst(format) => print_ast(line, number, format),
+        };
+        for err in result.err().unwrap_or_default().iter() {
+            let span = err.span;
+            let source = prompt_source(&sources, span);
+            report(
+                std::slice::from_ref(err),
+                "<stdin>",
+                source,
+                span.line,
+                format,
+            );
+        }
     }
 }
 
-fn run(source: String) {
-    println!("{}", source);
-    let tokens = match scan_tokens(source) {
-        Ok(tokens) => tokens,
-        Err(err) => {
-            println!("Error scanning: {}", err);
-            return;
+// the line typed at the prompt that the span points into. Errors raised in a
+// function declared on an earlier line point into that line.
+fn prompt_source<'src>(sources: &[&'src str], span: Span) -> &'src str {
+    span.line
+        .checked_sub(1)
+        .and_then(|i| sources.get(i))
+        .copied()
+        .unwrap_or_default()
+}
+
+// the tokens of the source starting on `line`, setting lexical errors aside
+fn tokens<'a, 'src: 'a>(
+    source: &'src str,
+    line: usize,
+    diagnostics: &'a mut Vec<LexError>,
+) -> impl Iterator<Item = Token<'src>> + 'a {
+    Lexer::starting_at_line(source, line)
+        .filter_map(|token| token.map_err(|err| diagnostics.push(err)).ok())
+}
+
+fn run<'src>(
+    interpreter: &mut Interpreter<'src>,
+    source: &'src str,
+    line: usize,
+) -> Result<(), Vec<LoxError>> {
+    // the parser pulls tokens from the lexer as it goes
+    let mut diagnostics: Vec<LexError> = Vec::new();
+    let (statements, errors) = Parser::new(tokens(source, line, &mut diagnostics)).parse();
+    if !diagnostics.is_empty() {
+        return Err(diagnostics.into_iter().map(LoxError::from).collect());
+    }
+    if !errors.is_empty() {
+        return Err(errors.into_iter().map(LoxError::from).collect());
+    }
+    let errors = resolve(&statements);
+    if !errors.is_empty() {
+        return Err(errors.into_iter().map(LoxError::from).collect());
+    }
+    interpreter
+        .interpret(&statements)
+        .map_err(|err| vec![LoxError::from(err)])
+}
+
+// like `run`, but a line holding a single expression has its value printed
+fn run_line<'src>(
+    interpreter: &mut Interpreter<'src>,
+    line: &'src str,
+    number: usize,
+) -> Result<(), Vec<LoxError>> {
+    let tokens: Result<Vec<Token>, LexError> = Lexer::starting_at_line(line, number).collect();
+    if let Ok(tokens) = tokens {
+        if let Ok(expr) = Parser::new(tokens).parse_expression() {
+            let errors = resolve_expression(&expr);
+            if !errors.is_empty() {
+                return Err(errors.into_iter().map(LoxError::from).collect());
+            }
+            let value = interpreter
+                .evaluate(&expr)
+                .map_err(|err| vec![LoxError::from(err)])?;
+            println!("{}", value);
+            return Ok(());
         }
-    };
-    for tok in tokens.iter() {
-        println!("token: {:?}", tok);
     }
-    let mut parser = Parser { current: 0, tokens };
-    let ast = parser.expression();
-    println!("AST -> {:#?}", ast)
+    run(interpreter, line, number)
+}
+
+// prints the syntax tree of the source, or of the expression if that's all it holds
+fn print_ast(source: &str, line: usize, format: AstFormat) -> Result<(), Vec<LoxError>> {
+    let mut diagnostics: Vec<LexError> = Vec::new();
+    let tokens: Vec<Token> = tokens(source, line, &mut diagnostics).collect();
+    if !diagnostics.is_empty() {
+        return Err(diagnostics.into_iter().map(LoxError::from).collect());
+    }
+    if let Ok(expr) = Parser::new(tokens.clone()).parse_expression() {
+        match format {
+            AstFormat::Lisp => println!("{}", AstPrinter.visit_expression(&expr)),
+            AstFormat::Rpn => println!("{}", RpnPrinter.visit_expression(&expr)),
+        }
+        return Ok(());
+    }
+    let (statements, errors) = Parser::new(tokens).parse();
+    if !errors.is_empty() {
+        return Err(errors.into_iter().map(LoxError::from).collect());
+    }
+    match format {
+        AstFormat::Lisp => println!("{}", AstPrinter::print(&statements)),
+        AstFormat::Rpn => println!("{}", RpnPrinter::print(&statements)),
+    }
+    Ok(())
+}
+
+fn format_source(source: &str) -> Result<String, Vec<LoxError>> {
+    let (tokens, trivia, diagnostics) = scan_tokens_lossless(source);
+    if !diagnostics.is_empty() {
+        return Err(diagnostics.into_iter().map(LoxError::from).collect());
+    }
+    let (statements, errors) = Parser::new(tokens.clone()).parse();
+    if !errors.is_empty() {
+        return Err(errors.into_iter().map(LoxError::from).collect());
+    }
+    Ok(Formatter::format(&statements, &tokens, &trivia))
+}
+
+// `first_line` is the line the source starts on
+fn report(errors: &[LoxError], file: &str, source: &str, first_line: usize, format: ErrorFormat) {
+    for err in errors.iter() {
+        match format {
+            ErrorFormat::Human { color } => {
+                let renderer = Renderer::new(file, source, color).with_first_line(first_line);
+                println!("{}\n", renderer.render(err))
+            }
+            ErrorFormat::Json => {
+                let renderer = Renderer::new(file, source, false).with_first_line(first_line);
+                eprintln!("{}", renderer.render_json(err))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lox_rust::syntax_tree::error::ParseErrorKind;
+
+    #[test]
+    fn test_run_line_rejects_invalid_assignment() {
+        let mut interpreter = Interpreter::new();
+
+        let errors = run_line(&mut interpreter, "1 + 2 = 3", 1).unwrap_err();
+
+        assert!(errors[0].kind == LoxErrorKind::Parse(ParseErrorKind::InvalidAssignmentTarget));
+    }
+
+    #[test]
+    fn test_print_ast_rejects_invalid_assignment() {
+        assert!(print_ast("1 + 2 = 3", 1, AstFormat::Lisp).is_err());
+        assert!(print_ast("1 + 2", 1, AstFormat::Lisp).is_ok());
+    }
+
+    #[test]
+    fn test_prompt_errors_point_into_their_own_line() {
+        let sources = ["fun f() { return -nil; }", "print \"abcdefghijé\"; f();"];
+        let mut interpreter = Interpreter::new();
+        run_line(&mut interpreter, sources[0], 1).unwrap();
+
+        let errors = run_line(&mut interpreter, sources[1], 2).unwrap_err();
+
+        let span = errors[0].span;
+        let source = prompt_source(&sources, span);
+        assert_eq!(source, sources[0]);
+        let rendered = Renderer::new("<stdin>", source, false)
+            .with_first_line(span.line)
+            .render(&errors[0]);
+        assert!(rendered.ends_with("1 | fun f() { return -nil; }\n  |                  ^"));
+    }
 }
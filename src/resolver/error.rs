@@ -0,0 +1,36 @@
+use crate::diagnostic::Diagnostic;
+use crate::diagnostic::ErrorKind;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResolveErrorKind {
+    // `var a = a;` inside a local scope
+    ReadInOwnInitializer,
+    // two declarations of the same name in one local scope
+    AlreadyDeclared,
+    TopLevelReturn,
+    ThisOutsideClass,
+    SuperOutsideClass,
+    SuperWithoutSuperclass,
+    InheritFromSelf,
+    // `return` with a value inside a class initializer
+    ReturnValueFromInitializer,
+}
+
+impl ErrorKind for ResolveErrorKind {
+    fn code(&self) -> &'static str {
+        match self {
+            ResolveErrorKind::ReadInOwnInitializer => "R0001",
+            ResolveErrorKind::AlreadyDeclared => "R0002",
+            ResolveErrorKind::TopLevelReturn => "R0003",
+            ResolveErrorKind::ThisOutsideClass => "R0004",
+            ResolveErrorKind::SuperOutsideClass => "R0005",
+            ResolveErrorKind::SuperWithoutSuperclass => "R0006",
+            ResolveErrorKind::InheritFromSelf => "R0007",
+            ResolveErrorKind::ReturnValueFromInitializer => "R0008",
+        }
+    }
+}
+
+// a misuse of a name or keyword found before running, pointing at the token
+// it was found at
+pub type ResolveError = Diagnostic<ResolveErrorKind>;
@@ -0,0 +1,444 @@
+pub mod error;
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::resolver::error::ResolveError;
+use crate::resolver::error::ResolveErrorKind;
+use crate::scanner::token::Token;
+use crate::syntax_tree::expression::Depth;
+use crate::syntax_tree::expression::Expression;
+use crate::syntax_tree::statement::FunctionDecl;
+use crate::syntax_tree::statement::Stmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FunctionType {
+    None,
+    // a function or a method other than `init`
+    Function,
+    Initializer,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ClassType {
+    None,
+    Class,
+    Subclass,
+}
+
+// statically walks a program before it runs, recording on every variable reference
+// how many scopes out its declaration lives, and reporting misuses of names that can
+// be detected without running the code
+struct Resolver {
+    // the local scopes currently being resolved, innermost last. A name maps to
+    // whether its initializer has been resolved yet. Globals are not tracked.
+    scopes: Vec<HashMap<String, bool>>,
+    current_function: FunctionType,
+    current_class: ClassType,
+    errors: Vec<ResolveError>,
+}
+
+// resolves the program, returning every static error found
+pub fn resolve(statements: &[Stmt]) -> Vec<ResolveError> {
+    let mut resolver = Resolver::new();
+    resolver.resolve_statements(statements);
+    resolver.errors
+}
+
+// resolves a single top-level expression, as typed at the REPL
+pub fn resolve_expression(expr: &Expression) -> Vec<ResolveError> {
+    let mut resolver = Resolver::new();
+    resolver.resolve_expression(expr);
+    resolver.errors
+}
+
+impl Resolver {
+    fn new() -> Resolver {
+        Resolver {
+            scopes: Vec::new(),
+            current_function: FunctionType::None,
+            current_class: ClassType::None,
+            errors: Vec::new(),
+        }
+    }
+
+    fn resolve_statements(&mut self, statements: &[Stmt]) {
+        for stmt in statements {
+            self.resolve_statement(stmt);
+        }
+    }
+
+    fn resolve_statement(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Block(statements) => {
+                self.begin_scope();
+                self.resolve_statements(statements);
+                self.end_scope();
+            }
+            Stmt::Class {
+                name,
+                superclass,
+                methods,
+            } => self.resolve_class(name, superclass.as_ref(), methods),
+            Stmt::Expression(expr) | Stmt::Print(expr) => self.resolve_expression(expr),
+            Stmt::Function(declaration) => {
+                // declared and defined eagerly so the function can refer to itself
+                self.declare(&declaration.name);
+                self.define(&declaration.name);
+                self.resolve_function(declaration, FunctionType::Function);
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.resolve_expression(condition);
+                self.resolve_statement(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.resolve_statement(else_branch);
+                }
+            }
+            Stmt::Return { keyword, value } => {
+                if self.current_function == FunctionType::None {
+                    self.error(
+                        ResolveErrorKind::TopLevelReturn,
+                        keyword,
+                        "Can't return from top-level code.",
+                    );
+                }
+                if let Some(value) = value {
+                    // initializers always return `this`, only a bare `return` may
+                    // leave them early
+                    if self.current_function == FunctionType::Initializer {
+                        self.errors.push(
+                            ResolveError::new(
+                                ResolveErrorKind::ReturnValueFromInitializer,
+                                keyword.span,
+                                String::from("Can't return a value from an initializer."),
+                            )
+                            .with_help(
+                                "initializers always return 'this', a bare 'return;' leaves early",
+                            ),
+                        );
+                    }
+                    self.resolve_expression(value);
+                }
+            }
+            Stmt::Var { name, initializer } => {
+                self.declare(name);
+                if let Some(initializer) = initializer {
+                    self.resolve_expression(initializer);
+                }
+                self.define(name);
+            }
+            Stmt::While { condition, body } => {
+                self.resolve_expression(condition);
+                self.resolve_statement(body);
+            }
+        }
+    }
+
+    fn resolve_class(
+        &mut self,
+        name: &Token,
+        superclass: Option<&Expression>,
+        methods: &[Rc<FunctionDecl>],
+    ) {
+        let enclosing_class = self.current_class;
+        self.current_class = ClassType::Class;
+
+        self.declare(name);
+        self.define(name);
+
+        if let Some(superclass) = superclass {
+            if let Expression::Variable {
+                name: superclass_name,
+                ..
+            } = superclass
+            {
+                if superclass_name.name() == name.name() {
+                    self.error(
+                        ResolveErrorKind::InheritFromSelf,
+                        superclass_name,
+                        "A class can't inherit from itself.",
+                    );
+                }
+            }
+            self.current_class = ClassType::Subclass;
+            self.resolve_expression(superclass);
+
+            self.begin_scope();
+            self.define_name("super");
+        }
+
+        self.begin_scope();
+        self.define_name("this");
+
+        for method in methods {
+            let function_type = if method.name.name() == "init" {
+                FunctionType::Initializer
+            } else {
+                FunctionType::Function
+            };
+            self.resolve_function(method, function_type);
+        }
+
+        self.end_scope();
+        if superclass.is_some() {
+            self.end_scope();
+        }
+
+        self.current_class = enclosing_class;
+    }
+
+    fn resolve_function(&mut self, declaration: &FunctionDecl, function_type: FunctionType) {
+        let enclosing_function = self.current_function;
+        self.current_function = function_type;
+
+        self.begin_scope();
+        for param in declaration.params.iter() {
+            self.declare(param);
+            self.define(param);
+        }
+        self.resolve_statements(&declaration.body);
+        self.end_scope();
+
+        self.current_function = enclosing_function;
+    }
+
+    fn resolve_expression(&mut self, expr: &Expression) {
+        match expr {
+            Expression::Assign {
+                name, value, depth, ..
+            } => {
+                self.resolve_expression(value);
+                self.resolve_local(name.name(), depth);
+            }
+            Expression::Binary { left, right, .. } | Expression::Logical { left, right, .. } => {
+                self.resolve_expression(left);
+                self.resolve_expression(right);
+            }
+            Expression::Call {
+                callee, arguments, ..
+            } => {
+                self.resolve_expression(callee);
+                for argument in arguments {
+                    self.resolve_expression(argument);
+                }
+            }
+            // properties are looked up dynamically, only the object is resolved
+            Expression::Get { object, .. } => self.resolve_expression(object),
+            Expression::Set { object, value, .. } => {
+                self.resolve_expression(value);
+                self.resolve_expression(object);
+            }
+            Expression::Grouping { expression, .. } => self.resolve_expression(expression),
+            Expression::Literal(_) => {}
+            Expression::Super { keyword, depth, .. } => {
+                match self.current_class {
+                    ClassType::None => self.error(
+                        ResolveErrorKind::SuperOutsideClass,
+                        keyword,
+                        "Can't use 'super' outside of a class.",
+                    ),
+                    ClassType::Class => self.error(
+                        ResolveErrorKind::SuperWithoutSuperclass,
+                        keyword,
+                        "Can't use 'super' in a class with no superclass.",
+                    ),
+                    ClassType::Subclass => {}
+                }
+                self.resolve_local("super", depth);
+            }
+            Expression::This { keyword, depth } => {
+                if self.current_class == ClassType::None {
+                    self.error(
+                        ResolveErrorKind::ThisOutsideClass,
+                        keyword,
+                        "Can't use 'this' outside of a class.",
+                    );
+                    return;
+                }
+                self.resolve_local("this", depth);
+            }
+            Expression::Unary { right, .. } => self.resolve_expression(right),
+            Expression::Variable { name, depth } => {
+                if let Some(false) = self.scopes.last().and_then(|scope| scope.get(name.name())) {
+                    self.error(
+                        ResolveErrorKind::ReadInOwnInitializer,
+                        name,
+                        "Can't read local variable in its own initializer.",
+                    );
+                }
+                self.resolve_local(name.name(), depth);
+            }
+        }
+    }
+
+    // records how many scopes out `name` is declared, leaving globals unresolved
+    fn resolve_local(&mut self, name: &str, depth: &Depth) {
+        for (distance, scope) in self.scopes.iter().rev().enumerate() {
+            if scope.contains_key(name) {
+                depth.set(Some(distance));
+                return;
+            }
+        }
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    // adds the name to the innermost scope, marked as not ready for use yet
+    fn declare(&mut self, name: &Token) {
+        let Some(scope) = self.scopes.last_mut() else {
+            return;
+        };
+        if scope.contains_key(name.name()) {
+            self.error(
+                ResolveErrorKind::AlreadyDeclared,
+                name,
+                "Already a variable with this name in this scope.",
+            );
+            return;
+        }
+        scope.insert(name.name().to_string(), false);
+    }
+
+    fn define(&mut self, name: &Token) {
+        self.define_name(name.name());
+    }
+
+    fn define_name(&mut self, name: &str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), true);
+        }
+    }
+
+    fn error(&mut self, kind: ResolveErrorKind, token: &Token, message: &str) {
+        self.errors
+            .push(ResolveError::new(kind, token.span, String::from(message)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::lexer::scan_tokens;
+    use crate::scanner::span::Span;
+    use crate::syntax_tree::parser::Parser;
+
+    fn resolve_source(source: &str) -> (Vec<Stmt<'_>>, Vec<ResolveError>) {
+        let tokens = scan_tokens(source).unwrap();
+        let (statements, errors) = Parser::new(tokens).parse();
+        assert!(errors.is_empty(), "unexpected parse errors: {:?}", errors);
+        let errors = resolve(&statements);
+        (statements, errors)
+    }
+
+    fn error_kinds(source: &str) -> Vec<ResolveErrorKind> {
+        resolve_source(source)
+            .1
+            .into_iter()
+            .map(|err| err.kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_resolve_depths() {
+        let (statements, errors) = resolve_source("var g; { var a; { a; g; } }");
+
+        assert!(errors.is_empty());
+        let Stmt::Block(outer) = &statements[1] else {
+            panic!("expected a block, got {:?}", statements[1]);
+        };
+        let Stmt::Block(inner) = &outer[1] else {
+            panic!("expected a block, got {:?}", outer[1]);
+        };
+        let depths: Vec<Option<usize>> = inner
+            .iter()
+            .map(|stmt| match stmt {
+                Stmt::Expression(Expression::Variable { depth, .. }) => depth.get(),
+                stmt => panic!("expected a variable, got {:?}", stmt),
+            })
+            .collect();
+        // `a` is one scope out, `g` is global and left unresolved
+        assert_eq!(depths, vec![Some(1), None]);
+    }
+
+    #[test]
+    fn test_resolve_read_in_own_initializer() {
+        let (_, errors) = resolve_source("var a = 1; { var a = a; }");
+
+        assert_eq!(
+            errors,
+            vec![ResolveError::new(
+                ResolveErrorKind::ReadInOwnInitializer,
+                Span::new(21, 22, 1, 22),
+                String::from("Can't read local variable in its own initializer."),
+            )]
+        );
+        // globals may refer to themselves
+        assert!(error_kinds("var b = b;").is_empty());
+    }
+
+    #[test]
+    fn test_resolve_redeclaration() {
+        assert_eq!(
+            error_kinds("fun f(a) { var a; } { var b; var b; }"),
+            vec![
+                ResolveErrorKind::AlreadyDeclared,
+                ResolveErrorKind::AlreadyDeclared
+            ]
+        );
+        assert!(error_kinds("var c; var c;").is_empty());
+    }
+
+    #[test]
+    fn test_resolve_return_value_from_initializer() {
+        assert_eq!(
+            error_kinds(
+                "class A { init() { fun f() { return 1; } return; } other() { return 2; } } \
+                 class B { init() { return 3; } }"
+            ),
+            vec![ResolveErrorKind::ReturnValueFromInitializer]
+        );
+        assert!(error_kinds("fun init() { return 1; }").is_empty());
+    }
+
+    #[test]
+    fn test_resolve_misplaced_keywords() {
+        assert_eq!(
+            error_kinds("return 1;"),
+            vec![ResolveErrorKind::TopLevelReturn]
+        );
+        assert_eq!(
+            error_kinds("print this;"),
+            vec![ResolveErrorKind::ThisOutsideClass]
+        );
+        assert_eq!(
+            error_kinds("fun f() { return this; }"),
+            vec![ResolveErrorKind::ThisOutsideClass]
+        );
+        assert_eq!(
+            error_kinds("super.x();"),
+            vec![ResolveErrorKind::SuperOutsideClass]
+        );
+        assert_eq!(
+            error_kinds("class A { m() { super.m(); } }"),
+            vec![ResolveErrorKind::SuperWithoutSuperclass]
+        );
+        assert_eq!(
+            error_kinds("class A < A {}"),
+            vec![ResolveErrorKind::InheritFromSelf]
+        );
+        assert!(error_kinds(
+            "class A { m() { return this; } } class B < A { m() { return super.m(); } }"
+        )
+        .is_empty());
+    }
+}
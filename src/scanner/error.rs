@@ -0,0 +1,26 @@
+use crate::diagnostic::Diagnostic;
+use crate::diagnostic::ErrorKind;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexErrorKind {
+    UnrecognizedCharacter,
+    UnterminatedString,
+    UnterminatedComment,
+    InvalidNumber,
+    InvalidEscape,
+}
+
+impl ErrorKind for LexErrorKind {
+    fn code(&self) -> &'static str {
+        match self {
+            LexErrorKind::UnterminatedString => "L0001",
+            LexErrorKind::UnrecognizedCharacter => "L0002",
+            LexErrorKind::UnterminatedComment => "L0003",
+            LexErrorKind::InvalidNumber => "L0004",
+            LexErrorKind::InvalidEscape => "L0005",
+        }
+    }
+}
+
+// source that couldn't be scanned into a token
+pub type LexError = Diagnostic<LexErrorKind>;
@@ -1,16 +1,234 @@
+use std::borrow::Cow;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+use unicode_normalization::is_nfc;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::scanner::error::LexError;
+use crate::scanner::error::LexErrorKind;
+use crate::scanner::span::Span;
 use crate::scanner::token::Literal;
 use crate::scanner::token::Token;
+use crate::scanner::token::TokenTrivia;
+use crate::scanner::token::Trivia;
+use crate::scanner::token::TriviaKind;
 use crate::scanner::token_type::TokenType;
 
-pub fn scan_tokens(source: String) -> Result<Vec<Token>, String> {
-    let mut chars = source.chars().peekable();
-    let mut tokens: Vec<Token> = Vec::new();
+// the type and optional literal of a scanned token, before its span is attached
+type ScanResult<'src> = Result<(TokenType, Option<Literal<'src>>), LexError>;
+
+// wraps the character iterator and keeps track of where we are in the source
+#[derive(Clone)]
+struct Cursor<'a> {
+    chars: Peekable<CharIndices<'a>>,
+    source_len: usize,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(source: &'a str) -> Cursor<'a> {
+        Cursor {
+            chars: source.char_indices().peekable(),
+            source_len: source.len(),
+            line: 1,
+            column: 1,
+        }
+    }
+
+    // byte offset of the next character to be consumed
+    fn offset(&mut self) -> usize {
+        match self.chars.peek() {
+            Some((offset, _)) => *offset,
+            None => self.source_len,
+        }
+    }
+
+    // an empty span at the next character to be consumed
+    fn position(&mut self) -> Span {
+        let offset = self.offset();
+        Span::new(offset, offset, self.line, self.column)
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().map(|(_, c)| *c)
+    }
 
-    while let Some(char) = chars.next() {
-        if char.is_whitespace() {
-            continue;
+    // looks two characters ahead without consuming anything
+    fn peek_next(&self) -> Option<char> {
+        let mut lookahead = self.chars.clone();
+        lookahead.next();
+        lookahead.next().map(|(_, c)| c)
+    }
+}
+
+impl<'a> Iterator for Cursor<'a> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let (_, c) = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+}
+
+// scans the source lazily, one token per call to `next`. Unscannable source is
+// yielded as an `Err`, after which scanning carries on with the following token.
+// The last item is always the EOF token.
+pub struct Lexer<'src> {
+    source: &'src str,
+    chars: Cursor<'src>,
+    // whether whitespace and comments are kept as trivia next to the tokens
+    lossless: bool,
+    // trivia seen since the last token
+    trivia: Vec<Trivia<'src>>,
+    finished: bool,
+}
+
+impl<'src> Lexer<'src> {
+    pub fn new(source: &'src str) -> Lexer<'src> {
+        Lexer {
+            source,
+            chars: Cursor::new(source),
+            lossless: false,
+            trivia: Vec::new(),
+            finished: false,
+        }
+    }
+
+    // a lexer for source that starts on `line` of a longer input, like a line typed
+    // at the prompt, so that spans tell which line they point into
+    pub fn starting_at_line(source: &'src str, line: usize) -> Lexer<'src> {
+        let mut lexer = Lexer::new(source);
+        lexer.chars.line = line;
+        lexer
+    }
+
+    // a lexer that keeps whitespace and comments as trivia instead of dropping them.
+    // Its iterator would throw the trivia away, so it is only driven through
+    // `scan_tokens_lossless`
+    fn lossless(source: &'src str) -> Lexer<'src> {
+        Lexer {
+            lossless: true,
+            ..Lexer::new(source)
+        }
+    }
+
+    // scans the next token and its trivia, keeping unscannable source as an `Error`
+    // token next to its diagnostic so that the token stream stays complete
+    fn next_token(&mut self) -> Option<(Token<'src>, TokenTrivia<'src>, Option<LexError>)> {
+        if self.finished {
+            return None;
+        }
+        self.skip_trivia();
+
+        let start = self.chars.position();
+        let Some(char) = self.chars.next() else {
+            self.finished = true;
+            let eof = Token::new(TokenType::Eof, "", None, start);
+            let trivia = TokenTrivia {
+                leading: std::mem::take(&mut self.trivia),
+                trailing: Vec::new(),
+            };
+            return Some((eof, trivia, None));
+        };
+        let token_result = self.scan_token(char, start);
+
+        let span = start.to(self.chars.position());
+        let lexeme = &self.source[span.start..span.end];
+        let (token, diagnostic) = match token_result {
+            Ok((token_type, literal)) => (Token::new(token_type, lexeme, literal, span), None),
+            Err(err) => (Token::new(TokenType::Error, lexeme, None, span), Some(err)),
+        };
+        let mut trivia = TokenTrivia {
+            leading: std::mem::take(&mut self.trivia),
+            trailing: Vec::new(),
+        };
+        if self.lossless {
+            trivia.trailing = self.trailing_trivia();
         }
-        let token_result: Result<Token, String> = match char {
+        Some((token, trivia, diagnostic))
+    }
+
+    // drops whitespace, or collects it and comments as leading trivia in lossless mode
+    fn skip_trivia(&mut self) {
+        if !self.lossless {
+            while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+                self.chars.next();
+            }
+            return;
+        }
+        while let Some(trivia) = self.scan_trivia() {
+            self.trivia.push(trivia);
+        }
+    }
+
+    // the trivia after a token up to the first line break belongs to that token
+    fn trailing_trivia(&mut self) -> Vec<Trivia<'src>> {
+        let mut trailing = Vec::new();
+        while self.chars.peek() != Some('\n') {
+            match self.scan_trivia() {
+                Some(trivia) => trailing.push(trivia),
+                None => break,
+            }
+        }
+        trailing
+    }
+
+    // scans a single line break, a run of other whitespace or a comment
+    fn scan_trivia(&mut self) -> Option<Trivia<'src>> {
+        let start = self.chars.offset();
+        let (line, column) = (self.chars.line, self.chars.column);
+        let kind = match self.chars.peek()? {
+            '\n' => {
+                self.chars.next();
+                TriviaKind::Newline
+            }
+            c if c.is_whitespace() => {
+                while self
+                    .chars
+                    .peek()
+                    .is_some_and(|c| c.is_whitespace() && c != '\n')
+                {
+                    self.chars.next();
+                }
+                TriviaKind::Whitespace
+            }
+            '/' if self.chars.peek_next() == Some('/') => {
+                skip_line_comment(&mut self.chars);
+                TriviaKind::LineComment
+            }
+            '/' if self.chars.peek_next() == Some('*') => {
+                // an unterminated comment is left for `scan_token` to report
+                let before = self.chars.clone();
+                self.chars.next();
+                self.chars.next();
+                if !skip_block_comment(&mut self.chars) {
+                    self.chars = before;
+                    return None;
+                }
+                TriviaKind::BlockComment
+            }
+            _ => return None,
+        };
+        let span = Span::new(start, self.chars.offset(), line, column);
+        Some(Trivia {
+            kind,
+            text: &self.source[span.start..span.end],
+            span,
+        })
+    }
+
+    // scans the rest of the token starting with `char`, which has already been consumed
+    fn scan_token(&mut self, char: char, start: Span) -> ScanResult<'src> {
+        let chars = &mut self.chars;
+        match char {
             '(' => make_simple_token(TokenType::LeftParen),
             ')' => make_simple_token(TokenType::RightParen),
             '{' => make_simple_token(TokenType::LeftBrace),
@@ -21,138 +239,449 @@ pub fn scan_tokens(source: String) -> Result<Vec<Token>, String> {
             '+' => make_simple_token(TokenType::Plus),
             ';' => make_simple_token(TokenType::Semicolon),
             '*' => make_simple_token(TokenType::Star),
-            '!' => double_lexeme(&mut chars, TokenType::Bang, TokenType::BangEqual),
-            '=' => double_lexeme(&mut chars, TokenType::Equal, TokenType::EqualEqual),
-            '<' => double_lexeme(&mut chars, TokenType::Less, TokenType::LessEqual),
-            '>' => double_lexeme(&mut chars, TokenType::Greater, TokenType::GreaterEqual),
+            '!' => double_lexeme(chars, TokenType::Bang, TokenType::BangEqual),
+            '=' => double_lexeme(chars, TokenType::Equal, TokenType::EqualEqual),
+            '<' => double_lexeme(chars, TokenType::Less, TokenType::LessEqual),
+            '>' => double_lexeme(chars, TokenType::Greater, TokenType::GreaterEqual),
             '/' => match chars.peek() {
-                Some(peeked_char) => match peeked_char {
-                    '/' => {
-                        // it's a comment...
-                        for next_char in chars.by_ref() {
-                            // consume all characters after the comment until the newline
-                            if next_char == '\n' {
-                                break;
-                            }
-                        }
+                Some('/') => {
+                    // it's a comment...
+                    skip_line_comment(chars);
+                    make_simple_token(TokenType::Comment)
+                }
+                Some('*') => {
+                    chars.next();
+                    if skip_block_comment(chars) {
                         make_simple_token(TokenType::Comment)
+                    } else {
+                        // pointing at the opening delimiter, not the rest of the source
+                        Err(LexError::new(
+                            LexErrorKind::UnterminatedComment,
+                            Span::new(start.start, start.start + 2, start.line, start.column),
+                            String::from("Unterminated block comment"),
+                        )
+                        .with_help("block comments nest, every '/*' needs its own '*/'"))
                     }
-                    _ => {
-                        chars.next();
-                        make_simple_token(TokenType::Slash)
-                    }
-                },
-                None => make_simple_token(TokenType::Slash),
+                }
+                _ => make_simple_token(TokenType::Slash),
             },
-            '"' => {
-                let mut elements: Vec<String> = Vec::new();
-                let mut terminated = false;
-                for next_char in chars.by_ref() {
-                    if next_char == '"' {
-                        terminated = true;
-                        break;
+            '"' => self.string(start),
+            '0'..='9' => self.number(char, start),
+            c if is_identifier_start(c) => self.identifier(start),
+            _ => Err(LexError::new(
+                LexErrorKind::UnrecognizedCharacter,
+                start.to(chars.position()),
+                format!("unrecognized character {:?}", char),
+            )),
+        }
+    }
+
+    // scans an identifier or keyword after its first character. Identifiers follow
+    // UAX #31, and are NFC normalized so that differently composed spellings of the
+    // same name refer to the same variable.
+    fn identifier(&mut self, start: Span) -> ScanResult<'src> {
+        while self.chars.peek().is_some_and(is_identifier_continue) {
+            self.chars.next();
+        }
+        let raw_token = &self.source[start.start..self.chars.offset()];
+
+        let typ = match raw_token {
+            "and" => TokenType::And,
+            "class" => TokenType::Class,
+            "else" => TokenType::Else,
+            "fun" => TokenType::Fun,
+            "for" => TokenType::For,
+            "if" => TokenType::If,
+            "nil" => TokenType::Nil,
+            "or" => TokenType::Or,
+            "print" => TokenType::Print,
+            "return" => TokenType::Return,
+            "super" => TokenType::Super,
+            "this" => TokenType::This,
+            "true" => TokenType::True,
+            "false" => TokenType::False,
+            "var" => TokenType::Var,
+            "while" => TokenType::While,
+            &_ => TokenType::Identifier,
+        };
+
+        let lit = if typ != TokenType::Identifier {
+            None
+        } else if is_nfc(raw_token) {
+            Some(Literal::Identifier(Cow::Borrowed(raw_token)))
+        } else {
+            Some(Literal::Identifier(Cow::Owned(raw_token.nfc().collect())))
+        };
+
+        Ok((typ, lit))
+    }
+
+    // scans a number literal after its first digit. A malformed literal is consumed
+    // up to its end so that its remains aren't scanned as separate tokens.
+    fn number(&mut self, first: char, start: Span) -> ScanResult<'src> {
+        let value = self.number_value(first, start);
+        if value.is_err() {
+            while self
+                .chars
+                .peek()
+                .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+            {
+                self.chars.next();
+            }
+        }
+        Ok((TokenType::Number, Some(Literal::Numeric(value?))))
+    }
+
+    fn number_value(&mut self, first: char, start: Span) -> Result<f64, LexError> {
+        if first == '0' {
+            let radix = match self.chars.peek() {
+                Some('x') => 16,
+                Some('o') => 8,
+                Some('b') => 2,
+                _ => 10,
+            };
+            if radix != 10 {
+                self.chars.next();
+                return self.radix_number(radix, start);
+            }
+        }
+
+        self.digits(10, true)?;
+        if self.chars.peek() == Some('.') {
+            self.chars.next();
+            if !self.chars.peek().is_some_and(|c| c.is_ascii_digit()) {
+                return Err(self.malformed_number(start, "Expect digits after the decimal point"));
+            }
+            self.digits(10, false)?;
+        }
+        if matches!(self.chars.peek(), Some('e' | 'E')) {
+            self.chars.next();
+            if matches!(self.chars.peek(), Some('+' | '-')) {
+                self.chars.next();
+            }
+            if !self.chars.peek().is_some_and(|c| c.is_ascii_digit()) {
+                return Err(self.malformed_number(start, "Expect digits in the exponent"));
+            }
+            self.digits(10, false)?;
+        }
+
+        let literal = &self.source[start.start..self.chars.offset()];
+        let literal = if literal.contains('_') {
+            Cow::Owned(literal.replace('_', ""))
+        } else {
+            Cow::Borrowed(literal)
+        };
+        literal
+            .parse::<f64>()
+            .map_err(|err| self.malformed_number(start, &format!("Unable to parse float: {}", err)))
+    }
+
+    // scans the digits of a `0x`, `0o` or `0b` literal after its prefix
+    fn radix_number(&mut self, radix: u32, start: Span) -> Result<f64, LexError> {
+        let (name, prefix) = match radix {
+            16 => ("hexadecimal", "0x"),
+            8 => ("octal", "0o"),
+            _ => ("binary", "0b"),
+        };
+        // a separator may follow the prefix, as in `0x_FF`
+        if self.digits(radix, true)? == 0 {
+            return Err(
+                self.malformed_number(start, &format!("Expect {} digits after '{}'", name, prefix))
+            );
+        }
+        // a letter or digit straight after the literal isn't part of this radix
+        if let Some(c) = self.chars.peek().filter(|c| c.is_ascii_alphanumeric()) {
+            return Err(LexError::new(
+                LexErrorKind::InvalidNumber,
+                char_span(self.chars.position(), c),
+                format!("Invalid digit '{}' in {} literal", c, name),
+            ));
+        }
+
+        let digits = &self.source[start.start + prefix.len()..self.chars.offset()];
+        Ok(digits
+            .chars()
+            .filter_map(|c| c.to_digit(radix))
+            .fold(0.0, |value, digit| value * radix as f64 + digit as f64))
+    }
+
+    // consumes digits of the given radix along with the `_` separators between them,
+    // returning the number of digits
+    fn digits(&mut self, radix: u32, mut after_digit: bool) -> Result<usize, LexError> {
+        let mut count = 0;
+        loop {
+            match self.chars.peek() {
+                Some('_') => {
+                    let at = self.chars.position();
+                    self.chars.next();
+                    if !after_digit || !self.chars.peek().is_some_and(|c| c.is_digit(radix)) {
+                        return Err(LexError::new(
+                            LexErrorKind::InvalidNumber,
+                            char_span(at, '_'),
+                            String::from("Digit separators must be placed between digits"),
+                        ));
                     }
-                    elements.push(next_char.to_string());
+                    after_digit = false;
                 }
-                if !terminated {
-                    return Err(String::from("Unterminated string"));
+                Some(c) if c.is_digit(radix) => {
+                    self.chars.next();
+                    count += 1;
+                    after_digit = true;
                 }
-                let joined = elements.join("");
-                Ok(Token {
-                    token_type: TokenType::String,
-                    literal: Some(Literal::String(joined)),
-                })
+                _ => return Ok(count),
             }
-            '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => {
-                let mut num_parts: Vec<String> = Vec::new();
-                let mut contains_dot = false;
-
-                num_parts.push(char.to_string());
-                for c in chars.by_ref() {
-                    if c == '.' && !contains_dot {
-                        contains_dot = true;
+        }
+    }
+
+    fn malformed_number(&mut self, start: Span, message: &str) -> LexError {
+        LexError::new(
+            LexErrorKind::InvalidNumber,
+            start.to(self.chars.position()),
+            String::from(message),
+        )
+    }
+
+    // scans a string literal after its opening quote. The contents are borrowed from
+    // the source unless escape sequences force a copy.
+    fn string(&mut self, start: Span) -> ScanResult<'src> {
+        let contents_start = self.chars.offset();
+        // only allocated once the first escape sequence is seen
+        let mut unescaped: Option<String> = None;
+        // the first invalid escape, reported once the whole string has been consumed
+        let mut invalid_escape: Option<LexError> = None;
+        loop {
+            let at = self.chars.position();
+            match self.chars.next() {
+                None => {
+                    return Err(LexError::new(
+                        LexErrorKind::UnterminatedString,
+                        start.to(self.chars.position()),
+                        String::from("Unterminated string"),
+                    )
+                    .with_help("add a closing '\"' to end the string"))
+                }
+                Some('"') => break,
+                Some('\\') => {
+                    let contents = unescaped
+                        .get_or_insert_with(|| self.source[contents_start..at.start].to_string());
+                    match self.escape() {
+                        Ok(c) => contents.push(c),
+                        Err(err) => {
+                            invalid_escape.get_or_insert(err);
+                        }
                     }
-                    if c != '.' && (c.is_alphabetic() || c.is_whitespace()) {
-                        break;
+                }
+                Some(c) => {
+                    if let Some(contents) = unescaped.as_mut() {
+                        contents.push(c);
                     }
-                    num_parts.push(c.to_string());
                 }
-
-                let lit = match num_parts.join("").to_string().parse::<f64>() {
-                    Ok(lit) => lit,
-                    Err(err) => return Err(format!("Unable to parse float: {}", err)),
-                };
-
-                Ok(Token {
-                    token_type: TokenType::Number,
-                    literal: Some(Literal::Numeric(lit)),
-                })
             }
-            'A'..='Z' | 'a'..='z' | '_' => {
-                let mut parts: Vec<String> = Vec::new();
+        }
+        if let Some(err) = invalid_escape {
+            return Err(err);
+        }
 
-                parts.push(char.to_string());
+        let contents = match unescaped {
+            Some(contents) => Cow::Owned(contents),
+            // the contents without the surrounding quotes
+            None => Cow::Borrowed(&self.source[contents_start..self.chars.offset() - 1]),
+        };
+        Ok((TokenType::String, Some(Literal::String(contents))))
+    }
 
-                for c in chars.by_ref() {
-                    if !(c.is_alphabetic()) && c != '_' {
-                        break;
-                    }
-                    parts.push(c.to_string());
+    // decodes the escape sequence after a backslash
+    fn escape(&mut self) -> Result<char, LexError> {
+        let at = self.chars.position();
+        let escaped = match self.chars.peek() {
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('0') => '\0',
+            Some('u') => {
+                self.chars.next();
+                return self.unicode_escape(at);
+            }
+            // the string is unterminated, which is reported instead
+            None => return Ok('\\'),
+            Some(c) => {
+                // a line break is left alone so that line tracking stays correct
+                if c != '\n' {
+                    self.chars.next();
                 }
-                let raw_token = parts.join("");
-
-                let typ = match raw_token.as_str() {
-                    "and" => TokenType::And,
-                    "class" => TokenType::Class,
-                    "else" => TokenType::Else,
-                    "fun" => TokenType::Fun,
-                    "for" => TokenType::For,
-                    "if" => TokenType::If,
-                    "nil" => TokenType::Nil,
-                    "or" => TokenType::Or,
-                    "print" => TokenType::Print,
-                    "return" => TokenType::Return,
-                    "super" => TokenType::Super,
-                    "this" => TokenType::This,
-                    "true" => TokenType::True,
-                    "false" => TokenType::False,
-                    "var" => TokenType::Var,
-                    "while" => TokenType::While,
-                    &_ => TokenType::Identifier,
-                };
-
-                let lit = if typ == TokenType::Identifier {
-                    Some(Literal::Identifier(raw_token))
-                } else {
-                    None
-                };
-
-                Ok(Token {
-                    token_type: typ,
-                    literal: lit,
-                })
+                return Err(LexError::new(
+                    LexErrorKind::InvalidEscape,
+                    char_span(at, c),
+                    format!("Unknown escape sequence '\\{}'", c.escape_debug()),
+                ));
             }
-            _ => Err(format!("unrecognized character {:?}", char)),
         };
+        self.chars.next();
+        Ok(escaped)
+    }
+
+    // decodes the `{XXXX}` part of a `\u{XXXX}` escape, where `u` is at `at`
+    fn unicode_escape(&mut self, at: Span) -> Result<char, LexError> {
+        if self.chars.peek() != Some('{') {
+            return Err(self.unexpected_in_escape(String::from("Expect '{' after '\\u'")));
+        }
+        self.chars.next();
 
-        match token_result {
-            Ok(t) => tokens.push(t),
-            Err(msg) => return Err(msg),
+        let digits_start = self.chars.position();
+        while self.chars.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+            self.chars.next();
         }
+        let digits_span = digits_start.to(self.chars.position());
+        let digits = &self.source[digits_span.start..digits_span.end];
+
+        if self.chars.peek() != Some('}') {
+            return Err(self.unexpected_in_escape(String::from(
+                "Expect hexadecimal digits followed by '}' in unicode escape",
+            )));
+        }
+        self.chars.next();
+        let escape_span = at.to(self.chars.position());
+
+        if digits.is_empty() {
+            return Err(LexError::new(
+                LexErrorKind::InvalidEscape,
+                escape_span,
+                String::from("Unicode escape must have at least one hexadecimal digit"),
+            ));
+        }
+        if digits.len() > 6 {
+            return Err(LexError::new(
+                LexErrorKind::InvalidEscape,
+                digits_span,
+                String::from("Unicode escape must have at most 6 hexadecimal digits"),
+            ));
+        }
+        u32::from_str_radix(digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| {
+                LexError::new(
+                    LexErrorKind::InvalidEscape,
+                    digits_span,
+                    format!("'{}' is not a valid unicode scalar value", digits),
+                )
+            })
+    }
+
+    // an error pointing at the next character, which is not consumed so that a
+    // closing quote still ends the string
+    fn unexpected_in_escape(&mut self, message: String) -> LexError {
+        let at = self.chars.position();
+        let span = match self.chars.peek() {
+            Some(c) => char_span(at, c),
+            None => at,
+        };
+        LexError::new(LexErrorKind::InvalidEscape, span, message)
+    }
+}
+
+impl<'src> Iterator for Lexer<'src> {
+    type Item = Result<Token<'src>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (token, _, diagnostic) = self.next_token()?;
+        match diagnostic {
+            Some(err) => Some(Err(err)),
+            None => Some(Ok(token)),
+        }
+    }
+}
+
+// scans the whole source and stops at the first lexical error
+pub fn scan_tokens(source: &str) -> Result<Vec<Token<'_>>, LexError> {
+    Lexer::new(source).collect()
+}
+
+// scans the whole source, recording every lexical error instead of bailing out.
+// Unscannable source is represented by an `Error` token so that the token stream stays complete.
+pub fn scan_tokens_with_diagnostics(source: &str) -> (Vec<Token<'_>>, Vec<LexError>) {
+    let (tokens, _, diagnostics) = scan(Lexer::new(source));
+    (tokens, diagnostics)
+}
+
+// like `scan_tokens_with_diagnostics`, but whitespace and comments are kept as trivia
+// instead of being dropped, so `lossless_source` can reproduce the source exactly.
+// The trivia of `tokens[i]` is `trivia[i]`. Comments don't produce `Comment` tokens
+// in this mode.
+pub fn scan_tokens_lossless(source: &str) -> (Vec<Token<'_>>, Vec<TokenTrivia<'_>>, Vec<LexError>) {
+    scan(Lexer::lossless(source))
+}
+
+// tokens borrow their lexemes, literals and trivia straight from the source, so
+// scanning only allocates the token and trivia vectors themselves
+fn scan<'src>(mut lexer: Lexer<'src>) -> (Vec<Token<'src>>, Vec<TokenTrivia<'src>>, Vec<LexError>) {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut trivia: Vec<TokenTrivia> = Vec::new();
+    let mut diagnostics: Vec<LexError> = Vec::new();
+    while let Some((token, token_trivia, diagnostic)) = lexer.next_token() {
+        tokens.push(token);
+        if lexer.lossless {
+            trivia.push(token_trivia);
+        }
+        diagnostics.extend(diagnostic);
     }
-    // add the trailing EOF token
-    tokens.push(Token {
-        token_type: TokenType::Eof,
-        literal: None,
-    });
-    Ok(tokens)
+    (tokens, trivia, diagnostics)
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c == '_' || unicode_ident::is_xid_start(c)
+}
+
+// XID_Continue includes digits and `_`
+fn is_identifier_continue(c: char) -> bool {
+    unicode_ident::is_xid_continue(c)
+}
+
+// the span of the character `c` found at `at`
+fn char_span(at: Span, c: char) -> Span {
+    Span::new(at.start, at.start + c.len_utf8(), at.line, at.column)
+}
+
+// consumes the rest of a `//` comment, leaving the line break in place
+fn skip_line_comment(chars: &mut Cursor) {
+    while let Some(next_char) = chars.peek() {
+        if next_char == '\n' {
+            break;
+        }
+        chars.next();
+    }
+}
+
+// consumes a `/* */` comment after its opening delimiter, including any comments
+// nested inside it. Returns false if the source ends before the comment does.
+fn skip_block_comment(chars: &mut Cursor) -> bool {
+    let mut depth = 1;
+    while let Some(next_char) = chars.next() {
+        match (next_char, chars.peek()) {
+            ('/', Some('*')) => {
+                chars.next();
+                depth += 1;
+            }
+            ('*', Some('/')) => {
+                chars.next();
+                depth -= 1;
+                if depth == 0 {
+                    return true;
+                }
+            }
+            _ => {}
+        }
+    }
+    false
 }
 
 fn double_lexeme(
-    chars: &mut std::iter::Peekable<std::str::Chars>,
+    chars: &mut Cursor,
     single_type: TokenType,
     double_type: TokenType,
-) -> Result<Token, String> {
+) -> ScanResult<'static> {
     let tt = match chars.peek() {
         Some('=') => {
             chars.next();
@@ -161,17 +690,11 @@ fn double_lexeme(
         None => single_type,
         _ => single_type,
     };
-    Ok(Token {
-        token_type: tt,
-        literal: None,
-    })
+    Ok((tt, None))
 }
 
-fn make_simple_token(tt: TokenType) -> Result<Token, String> {
-    Ok(Token {
-        token_type: tt,
-        literal: None,
-    })
+fn make_simple_token(tt: TokenType) -> ScanResult<'static> {
+    Ok((tt, None))
 }
 
 #[cfg(test)]
@@ -179,29 +702,24 @@ mod tests {
     use core::panic;
 
     use super::*;
+    use crate::scanner::token::lossless_source;
 
-    fn make_test_token(tt: TokenType) -> Token {
-        Token {
-            token_type: tt,
-            literal: None,
-        }
+    fn make_test_token(tt: TokenType) -> Token<'static> {
+        Token::new(tt, "", None, Span::new(0, 0, 0, 0))
     }
 
-    const EOF: Token = Token {
-        token_type: TokenType::Eof,
-        literal: None,
-    };
+    const EOF: Token<'static> = Token::new(TokenType::Eof, "", None, Span::new(0, 0, 0, 0));
 
     #[test]
     fn test_scan_tokens_all_success() {
-        let tokens = match scan_tokens(String::from(
+        let tokens = match scan_tokens(
             "   !,.- + != <= >=\n\n\n\n ==\t !\r<>}{()   / //\n;\"fo\no\" 1 6.78",
-        )) {
+        ) {
             Ok(tokens) => tokens,
             Err(err) => panic!("Unexpected error in test: {}", err),
         };
 
-        let expected_tokens = vec![
+        let expected_tokens = [
             make_test_token(TokenType::Bang),
             make_test_token(TokenType::Comma),
             make_test_token(TokenType::Dot),
@@ -221,18 +739,24 @@ mod tests {
             make_test_token(TokenType::Slash),
             make_test_token(TokenType::Comment),
             make_test_token(TokenType::Semicolon),
-            Token {
-                token_type: TokenType::String,
-                literal: Some(Literal::String(String::from("fo\no"))),
-            },
-            Token {
-                token_type: TokenType::Number,
-                literal: Some(Literal::Numeric(1.0)),
-            },
-            Token {
-                token_type: TokenType::Number,
-                literal: Some(Literal::Numeric(6.78)),
-            },
+            Token::new(
+                TokenType::String,
+                "",
+                Some(Literal::String(Cow::Borrowed("fo\no"))),
+                Span::new(0, 0, 0, 0),
+            ),
+            Token::new(
+                TokenType::Number,
+                "",
+                Some(Literal::Numeric(1.0)),
+                Span::new(0, 0, 0, 0),
+            ),
+            Token::new(
+                TokenType::Number,
+                "",
+                Some(Literal::Numeric(6.78)),
+                Span::new(0, 0, 0, 0),
+            ),
             EOF,
         ];
 
@@ -246,24 +770,25 @@ mod tests {
 
     #[test]
     fn test_scan_tokens_unexpected_character() {
-        match scan_tokens(String::from("?")) {
+        match scan_tokens("?") {
             Ok(tokens) => {
                 assert!(tokens.is_empty())
             }
             Err(err) => {
-                assert_eq!("unrecognized character '?'", err)
+                assert_eq!(err.kind, LexErrorKind::UnrecognizedCharacter);
+                assert_eq!("[1:1] unrecognized character '?'", err.to_string())
             }
         };
     }
 
     #[test]
     fn test_scan_tokens_comments() {
-        let tokens = match scan_tokens(String::from("// a comment \n+")) {
+        let tokens = match scan_tokens("// a comment \n+") {
             Ok(tokens) => tokens,
             Err(err) => panic!("Unexpected error in test: {}", err),
         };
 
-        let expected_tokens = vec![
+        let expected_tokens = [
             make_test_token(TokenType::Comment),
             make_test_token(TokenType::Plus),
             EOF,
@@ -277,18 +802,55 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_scan_tokens_nested_block_comments() {
+        let tokens = scan_tokens("/* a /* b\n */ c\n*/ + /**/-").unwrap();
+
+        let expected_types = [
+            TokenType::Comment,
+            TokenType::Plus,
+            TokenType::Comment,
+            TokenType::Minus,
+            TokenType::Eof,
+        ];
+        let types: Vec<TokenType> = tokens.iter().map(|t| t.token_type).collect();
+        assert_eq!(types, expected_types);
+        assert_eq!(tokens[0].lexeme, "/* a /* b\n */ c\n*/");
+        // positions after the comment account for the lines inside it
+        assert_eq!(tokens[1].span, Span::new(19, 20, 3, 4));
+    }
+
+    #[test]
+    fn test_scan_tokens_unterminated_block_comment() {
+        let (tokens, diagnostics) = scan_tokens_with_diagnostics("1 /* a /* b */\n c");
+
+        assert_eq!(
+            diagnostics,
+            vec![LexError::new(
+                LexErrorKind::UnterminatedComment,
+                Span::new(2, 4, 1, 3),
+                String::from("Unterminated block comment"),
+            )
+            .with_help("block comments nest, every '/*' needs its own '*/'")]
+        );
+        let types: Vec<TokenType> = tokens.iter().map(|t| t.token_type).collect();
+        assert_eq!(types, [TokenType::Number, TokenType::Error, TokenType::Eof]);
+    }
+
     #[test]
     fn test_scan_tokens_literals() {
-        let tokens = match scan_tokens(String::from("\"helloworld\"")) {
+        let tokens = match scan_tokens("\"helloworld\"") {
             Ok(tokens) => tokens,
             Err(err) => panic!("Unexpected error in test: {}", err),
         };
 
-        let expected_tokens = vec![
-            Token {
-                token_type: TokenType::String,
-                literal: Some(Literal::String(String::from("helloworld"))),
-            },
+        let expected_tokens = [
+            Token::new(
+                TokenType::String,
+                "",
+                Some(Literal::String(Cow::Borrowed("helloworld"))),
+                Span::new(0, 0, 0, 0),
+            ),
             EOF,
         ];
 
@@ -302,26 +864,149 @@ mod tests {
 
     #[test]
     fn test_scan_tokens_unterminated_string() {
-        match scan_tokens(String::from("\"helloworld")) {
+        match scan_tokens("\"helloworld") {
             Ok(tokens) => assert!(tokens.is_empty()),
             Err(err) => {
-                assert_eq!(err, "Unterminated string")
+                assert_eq!(err.kind, LexErrorKind::UnterminatedString);
+                assert_eq!(err.to_string(), "[1:1] Unterminated string")
             }
         };
     }
 
+    #[test]
+    fn test_scan_tokens_string_escapes() {
+        let tokens = scan_tokens(r#""q\"b\\s\n\t\r\0 \u{48}\u{1F600}" "plain""#).unwrap();
+
+        assert_eq!(
+            tokens[0].literal,
+            Some(Literal::String(Cow::Owned(String::from(
+                "q\"b\\s\n\t\r\0 H\u{1F600}"
+            ))))
+        );
+        assert_eq!(tokens[0].lexeme, r#""q\"b\\s\n\t\r\0 \u{48}\u{1F600}""#);
+        // strings without escapes still borrow from the source
+        assert!(matches!(
+            tokens[1].literal,
+            Some(Literal::String(Cow::Borrowed("plain")))
+        ));
+    }
+
+    // whether `text` is a slice of `source` rather than a copy of it
+    fn points_into(source: &str, text: &str) -> bool {
+        let range = source.as_bytes().as_ptr_range();
+        range.contains(&text.as_ptr()) && text.as_ptr() as usize + text.len() <= range.end as usize
+    }
+
+    #[test]
+    fn test_scan_tokens_borrow_from_source() {
+        let source = "var name = \"plain\" + \"esc\\n\" + 1_000 + 12; e\u{301}t\u{e9};";
+        let tokens = scan_tokens(source).unwrap();
+
+        // every lexeme is a slice of the source, escaped or not
+        for token in tokens.iter().filter(|t| t.token_type != TokenType::Eof) {
+            assert!(points_into(source, token.lexeme), "{:?}", token.lexeme);
+        }
+
+        // identifiers already in NFC and strings without escapes borrow their literal
+        match &tokens[1].literal {
+            Some(Literal::Identifier(Cow::Borrowed(name))) => assert!(points_into(source, name)),
+            other => panic!("expected a borrowed identifier, got {:?}", other),
+        }
+        match &tokens[3].literal {
+            Some(Literal::String(Cow::Borrowed(text))) => {
+                assert_eq!(*text, "plain");
+                assert!(points_into(source, text));
+            }
+            other => panic!("expected a borrowed string, got {:?}", other),
+        }
+
+        // only decoding escapes or normalizing a name allocates
+        assert!(matches!(
+            &tokens[5].literal,
+            Some(Literal::String(Cow::Owned(text))) if text == "esc\n"
+        ));
+        assert!(matches!(
+            &tokens[11].literal,
+            Some(Literal::Identifier(Cow::Owned(name))) if name == "\u{e9}t\u{e9}"
+        ));
+    }
+
+    #[test]
+    fn test_scan_tokens_invalid_escapes() {
+        let cases = [
+            (
+                r#""a\qb""#,
+                Span::new(3, 4, 1, 4),
+                "Unknown escape sequence '\\q'",
+            ),
+            (
+                r#""\u0041""#,
+                Span::new(3, 4, 1, 4),
+                "Expect '{' after '\\u'",
+            ),
+            (
+                r#""\u{4G}""#,
+                Span::new(5, 6, 1, 6),
+                "Expect hexadecimal digits followed by '}' in unicode escape",
+            ),
+            (
+                r#""\u{}""#,
+                Span::new(2, 5, 1, 3),
+                "Unicode escape must have at least one hexadecimal digit",
+            ),
+            (
+                r#""\u{1234567}""#,
+                Span::new(4, 11, 1, 5),
+                "Unicode escape must have at most 6 hexadecimal digits",
+            ),
+            (
+                r#""\u{D800}""#,
+                Span::new(4, 8, 1, 5),
+                "'D800' is not a valid unicode scalar value",
+            ),
+        ];
+
+        for (source, span, message) in cases {
+            let (tokens, diagnostics) = scan_tokens_with_diagnostics(source);
+            assert_eq!(
+                diagnostics,
+                vec![LexError::new(
+                    LexErrorKind::InvalidEscape,
+                    span,
+                    String::from(message)
+                )],
+                "{}",
+                source
+            );
+            // the whole string is still consumed as a single token
+            assert_eq!(tokens.len(), 2);
+            assert_eq!(tokens[0].token_type, TokenType::Error);
+            assert_eq!(tokens[0].lexeme, source);
+        }
+    }
+
+    #[test]
+    fn test_scan_tokens_escaped_quote_at_end_is_unterminated() {
+        let err = scan_tokens(r#""abc\""#).unwrap_err();
+
+        assert_eq!(err.kind, LexErrorKind::UnterminatedString);
+        assert_eq!(err.span, Span::new(0, 6, 1, 1));
+    }
+
     #[test]
     fn test_scan_tokens_multiline_literals() {
-        let tokens = match scan_tokens(String::from("\"hello\nworld\"")) {
+        let tokens = match scan_tokens("\"hello\nworld\"") {
             Ok(tokens) => tokens,
             Err(err) => panic!("Unexpected error in test: {}", err),
         };
 
-        let expected_tokens = vec![
-            Token {
-                token_type: TokenType::String,
-                literal: Some(Literal::String(String::from("hello\nworld"))),
-            },
+        let expected_tokens = [
+            Token::new(
+                TokenType::String,
+                "",
+                Some(Literal::String(Cow::Borrowed("hello\nworld"))),
+                Span::new(0, 0, 0, 0),
+            ),
             EOF,
         ];
 
@@ -334,32 +1019,42 @@ mod tests {
 
     #[test]
     fn test_scan_tokens_number_literals() {
-        let tokens = match scan_tokens(String::from("1.0 23433434.1233456 1 542 2348923409")) {
+        let tokens = match scan_tokens("1.0 23433434.1233456 1 542 2348923409") {
             Ok(tokens) => tokens,
             Err(err) => panic!("Unexpected error in test: {}", err),
         };
 
-        let expected_tokens = vec![
-            Token {
-                token_type: TokenType::Number,
-                literal: Some(Literal::Numeric(1.0)),
-            },
-            Token {
-                token_type: TokenType::Number,
-                literal: Some(Literal::Numeric(23433434.1233456)),
-            },
-            Token {
-                token_type: TokenType::Number,
-                literal: Some(Literal::Numeric(1.0)),
-            },
-            Token {
-                token_type: TokenType::Number,
-                literal: Some(Literal::Numeric(542.0)),
-            },
-            Token {
-                token_type: TokenType::Number,
-                literal: Some(Literal::Numeric(2348923409.0)),
-            },
+        let expected_tokens = [
+            Token::new(
+                TokenType::Number,
+                "",
+                Some(Literal::Numeric(1.0)),
+                Span::new(0, 0, 0, 0),
+            ),
+            Token::new(
+                TokenType::Number,
+                "",
+                Some(Literal::Numeric(23433434.1233456)),
+                Span::new(0, 0, 0, 0),
+            ),
+            Token::new(
+                TokenType::Number,
+                "",
+                Some(Literal::Numeric(1.0)),
+                Span::new(0, 0, 0, 0),
+            ),
+            Token::new(
+                TokenType::Number,
+                "",
+                Some(Literal::Numeric(542.0)),
+                Span::new(0, 0, 0, 0),
+            ),
+            Token::new(
+                TokenType::Number,
+                "",
+                Some(Literal::Numeric(2348923409.0)),
+                Span::new(0, 0, 0, 0),
+            ),
             EOF,
         ];
 
@@ -371,88 +1066,134 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_scan_tokens_number_literal_syntax() {
+        let tokens =
+            scan_tokens("0xFF 0o17 0b1010 1_000_000 1.5e-3 2E10 6.25e+2 0xdead_beef 0x_FF_FF")
+                .unwrap();
+        let values: Vec<Option<Literal>> = tokens.into_iter().map(|t| t.literal).collect();
+        assert_eq!(
+            values,
+            vec![
+                Some(Literal::Numeric(255.0)),
+                Some(Literal::Numeric(15.0)),
+                Some(Literal::Numeric(10.0)),
+                Some(Literal::Numeric(1_000_000.0)),
+                Some(Literal::Numeric(0.0015)),
+                Some(Literal::Numeric(2e10)),
+                Some(Literal::Numeric(625.0)),
+                Some(Literal::Numeric(3_735_928_559.0)),
+                Some(Literal::Numeric(65_535.0)),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_tokens_malformed_numbers() {
+        let cases = [
+            (
+                "1.",
+                Span::new(0, 2, 1, 1),
+                "Expect digits after the decimal point",
+            ),
+            ("1e", Span::new(0, 2, 1, 1), "Expect digits in the exponent"),
+            (
+                "2.5e+",
+                Span::new(0, 5, 1, 1),
+                "Expect digits in the exponent",
+            ),
+            (
+                "0x",
+                Span::new(0, 2, 1, 1),
+                "Expect hexadecimal digits after '0x'",
+            ),
+            (
+                "0b102",
+                Span::new(4, 5, 1, 5),
+                "Invalid digit '2' in binary literal",
+            ),
+            (
+                "0o8",
+                Span::new(0, 2, 1, 1),
+                "Expect octal digits after '0o'",
+            ),
+            (
+                "1__0",
+                Span::new(1, 2, 1, 2),
+                "Digit separators must be placed between digits",
+            ),
+            (
+                "0x_",
+                Span::new(2, 3, 1, 3),
+                "Digit separators must be placed between digits",
+            ),
+            (
+                "0b__1",
+                Span::new(2, 3, 1, 3),
+                "Digit separators must be placed between digits",
+            ),
+            (
+                "10_",
+                Span::new(2, 3, 1, 3),
+                "Digit separators must be placed between digits",
+            ),
+        ];
+
+        for (source, span, message) in cases {
+            let (tokens, diagnostics) = scan_tokens_with_diagnostics(source);
+            assert_eq!(
+                diagnostics,
+                vec![LexError::new(
+                    LexErrorKind::InvalidNumber,
+                    span,
+                    String::from(message)
+                )],
+                "{}",
+                source
+            );
+            // the malformed literal is swallowed whole
+            assert_eq!(tokens.len(), 2, "{}", source);
+            assert_eq!(tokens[0].lexeme, source);
+        }
+    }
+
     #[test]
     fn test_scan_tokens_identifiers_keywords() {
-        let tokens = match scan_tokens(String::from(
-            "test and class else fun for if nil or print return super this true false var while _my_variable_",
-        )) {
+        let tokens = match scan_tokens("test and class else fun for if nil or print return super this true false var while _my_variable_") {
             Ok(tokens) => tokens,
             Err(err) => panic!("Unexpected error in test: {}", err),
         };
 
-        let expected_tokens = vec![
-            Token {
-                token_type: TokenType::Identifier,
-                literal: Some(Literal::Identifier(String::from("test"))),
-            },
-            Token {
-                token_type: TokenType::And,
-                literal: None,
-            },
-            Token {
-                token_type: TokenType::Class,
-                literal: None,
-            },
-            Token {
-                token_type: TokenType::Else,
-                literal: None,
-            },
-            Token {
-                token_type: TokenType::Fun,
-                literal: None,
-            },
-            Token {
-                token_type: TokenType::For,
-                literal: None,
-            },
-            Token {
-                token_type: TokenType::If,
-                literal: None,
-            },
-            Token {
-                token_type: TokenType::Nil,
-                literal: None,
-            },
-            Token {
-                token_type: TokenType::Or,
-                literal: None,
-            },
-            Token {
-                token_type: TokenType::Print,
-                literal: None,
-            },
-            Token {
-                token_type: TokenType::Return,
-                literal: None,
-            },
-            Token {
-                token_type: TokenType::Super,
-                literal: None,
-            },
-            Token {
-                token_type: TokenType::This,
-                literal: None,
-            },
-            Token {
-                token_type: TokenType::True,
-                literal: None,
-            },
-            Token {
-                token_type: TokenType::False,
-                literal: None,
-            },
-            Token {
-                token_type: TokenType::Var,
-                literal: None,
-            },
-            Token {
-                token_type: TokenType::While,
-                literal: None,
-            },
-            Token {
-                token_type: TokenType::Identifier,
-                literal: Some(Literal::Identifier(String::from("_my_variable_"))),
-            },
+        let expected_tokens = [
+            Token::new(
+                TokenType::Identifier,
+                "",
+                Some(Literal::Identifier(Cow::Borrowed("test"))),
+                Span::new(0, 0, 0, 0),
+            ),
+            Token::new(TokenType::And, "", None, Span::new(0, 0, 0, 0)),
+            Token::new(TokenType::Class, "", None, Span::new(0, 0, 0, 0)),
+            Token::new(TokenType::Else, "", None, Span::new(0, 0, 0, 0)),
+            Token::new(TokenType::Fun, "", None, Span::new(0, 0, 0, 0)),
+            Token::new(TokenType::For, "", None, Span::new(0, 0, 0, 0)),
+            Token::new(TokenType::If, "", None, Span::new(0, 0, 0, 0)),
+            Token::new(TokenType::Nil, "", None, Span::new(0, 0, 0, 0)),
+            Token::new(TokenType::Or, "", None, Span::new(0, 0, 0, 0)),
+            Token::new(TokenType::Print, "", None, Span::new(0, 0, 0, 0)),
+            Token::new(TokenType::Return, "", None, Span::new(0, 0, 0, 0)),
+            Token::new(TokenType::Super, "", None, Span::new(0, 0, 0, 0)),
+            Token::new(TokenType::This, "", None, Span::new(0, 0, 0, 0)),
+            Token::new(TokenType::True, "", None, Span::new(0, 0, 0, 0)),
+            Token::new(TokenType::False, "", None, Span::new(0, 0, 0, 0)),
+            Token::new(TokenType::Var, "", None, Span::new(0, 0, 0, 0)),
+            Token::new(TokenType::While, "", None, Span::new(0, 0, 0, 0)),
+            Token::new(
+                TokenType::Identifier,
+                "",
+                Some(Literal::Identifier(Cow::Borrowed("_my_variable_"))),
+                Span::new(0, 0, 0, 0),
+            ),
             EOF,
         ];
 
@@ -463,4 +1204,232 @@ mod tests {
             assert_eq!(tokens[i].literal, expected_tokens[i].literal);
         }
     }
+
+    #[test]
+    fn test_scan_tokens_unicode_identifiers() {
+        let tokens = scan_tokens("x1 _2 \u{3bb}\u{3bc} na\u{ef}ve e\u{301}t\u{e9}").unwrap();
+
+        let names: Vec<&str> = tokens.iter().map(|t| t.name()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "x1",
+                "_2",
+                "\u{3bb}\u{3bc}",
+                "na\u{ef}ve",
+                "\u{e9}t\u{e9}",
+                ""
+            ]
+        );
+        // the lexeme keeps the source spelling, only the name is normalized
+        assert_eq!(tokens[4].lexeme, "e\u{301}t\u{e9}");
+        assert!(matches!(
+            tokens[0].literal,
+            Some(Literal::Identifier(Cow::Borrowed(_)))
+        ));
+    }
+
+    #[test]
+    fn test_scan_tokens_rejects_non_identifier_characters() {
+        let err = scan_tokens("a \u{2603}").unwrap_err();
+
+        assert_eq!(err.kind, LexErrorKind::UnrecognizedCharacter);
+        assert_eq!(err.span, Span::new(2, 5, 1, 3));
+    }
+
+    #[test]
+    fn test_scan_tokens_spans() {
+        let tokens = match scan_tokens("var x = 12.5;\n  \"a\nb\" != y // done\n") {
+            Ok(tokens) => tokens,
+            Err(err) => panic!("Unexpected error in test: {}", err),
+        };
+
+        let expected_spans = [
+            Span::new(0, 3, 1, 1),
+            Span::new(4, 5, 1, 5),
+            Span::new(6, 7, 1, 7),
+            Span::new(8, 12, 1, 9),
+            Span::new(12, 13, 1, 13),
+            Span::new(16, 21, 2, 3),
+            Span::new(22, 24, 3, 4),
+            Span::new(25, 26, 3, 7),
+            Span::new(27, 34, 3, 9),
+            Span::new(35, 35, 4, 1),
+        ];
+
+        assert_eq!(tokens.len(), expected_spans.len());
+
+        for (i, _) in tokens.iter().enumerate() {
+            assert_eq!(tokens[i].span, expected_spans[i]);
+        }
+    }
+
+    #[test]
+    fn test_scan_tokens_adjacent_tokens() {
+        let tokens = match scan_tokens("(12)+ab/3") {
+            Ok(tokens) => tokens,
+            Err(err) => panic!("Unexpected error in test: {}", err),
+        };
+
+        let expected_types = [
+            TokenType::LeftParen,
+            TokenType::Number,
+            TokenType::RightParen,
+            TokenType::Plus,
+            TokenType::Identifier,
+            TokenType::Slash,
+            TokenType::Number,
+            TokenType::Eof,
+        ];
+
+        assert_eq!(tokens.len(), expected_types.len());
+
+        for (i, _) in tokens.iter().enumerate() {
+            assert_eq!(tokens[i].token_type, expected_types[i]);
+        }
+    }
+
+    #[test]
+    fn test_scan_tokens_with_diagnostics_reports_all_errors() {
+        let (tokens, diagnostics) = scan_tokens_with_diagnostics("1 ? +\n# \"open");
+
+        let expected_types = [
+            TokenType::Number,
+            TokenType::Error,
+            TokenType::Plus,
+            TokenType::Error,
+            TokenType::Error,
+            TokenType::Eof,
+        ];
+
+        assert_eq!(tokens.len(), expected_types.len());
+
+        for (i, _) in tokens.iter().enumerate() {
+            assert_eq!(tokens[i].token_type, expected_types[i]);
+        }
+
+        let expected_diagnostics = [
+            LexError::new(
+                LexErrorKind::UnrecognizedCharacter,
+                Span::new(2, 3, 1, 3),
+                String::from("unrecognized character '?'"),
+            ),
+            LexError::new(
+                LexErrorKind::UnrecognizedCharacter,
+                Span::new(6, 7, 2, 1),
+                String::from("unrecognized character '#'"),
+            ),
+            LexError::new(
+                LexErrorKind::UnterminatedString,
+                Span::new(8, 13, 2, 3),
+                String::from("Unterminated string"),
+            )
+            .with_help("add a closing '\"' to end the string"),
+        ];
+
+        assert_eq!(diagnostics, expected_diagnostics);
+    }
+
+    #[test]
+    fn test_lexer_yields_tokens_lazily() {
+        let mut lexer = Lexer::new("print 1; ? 2");
+
+        let first = lexer.next().unwrap().unwrap();
+        assert_eq!(first.token_type, TokenType::Print);
+        assert_eq!(first.lexeme, "print");
+
+        let rest: Vec<Result<TokenType, LexErrorKind>> = lexer
+            .map(|item| item.map(|t| t.token_type).map_err(|err| err.kind))
+            .collect();
+        assert_eq!(
+            rest,
+            vec![
+                Ok(TokenType::Number),
+                Ok(TokenType::Semicolon),
+                Err(LexErrorKind::UnrecognizedCharacter),
+                Ok(TokenType::Number),
+                Ok(TokenType::Eof),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_stops_after_eof() {
+        let mut lexer = Lexer::new("");
+
+        assert_eq!(lexer.next().unwrap().unwrap().token_type, TokenType::Eof);
+        assert!(lexer.next().is_none());
+        assert!(lexer.next().is_none());
+    }
+
+    #[test]
+    fn test_scan_tokens_lossless_round_trip() {
+        let source = "  // leading comment\r\nvar  x = \"a b\";   // trailing\n\n\tprint x ; ? \n";
+
+        let (tokens, trivia, diagnostics) = scan_tokens_lossless(source);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(lossless_source(&tokens, &trivia), source);
+        assert!(tokens.iter().all(|t| t.token_type != TokenType::Comment));
+    }
+
+    #[test]
+    fn test_scan_tokens_lossless_trivia_attachment() {
+        let (tokens, trivia, _) = scan_tokens_lossless("a // one\n  // two\nb");
+
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(trivia.len(), 3);
+        let trailing: Vec<TriviaKind> = trivia[0].trailing.iter().map(|t| t.kind).collect();
+        assert_eq!(
+            trailing,
+            vec![TriviaKind::Whitespace, TriviaKind::LineComment]
+        );
+
+        let leading: Vec<&str> = trivia[1].leading.iter().map(|t| t.text).collect();
+        assert_eq!(leading, vec!["\n", "  ", "// two", "\n"]);
+        assert_eq!(tokens[1].lexeme, "b");
+        assert!(trivia[2].leading.is_empty());
+    }
+
+    #[test]
+    fn test_scan_tokens_lossless_block_comments() {
+        let source = "a /* x\n /* y */ */\n/* z */ b /* open";
+
+        let (tokens, trivia, diagnostics) = scan_tokens_lossless(source);
+
+        assert_eq!(lossless_source(&tokens, &trivia), source);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].kind, LexErrorKind::UnterminatedComment);
+        assert_eq!(trivia[0].trailing[1].text, "/* x\n /* y */ */");
+        assert_eq!(trivia[0].trailing[1].kind, TriviaKind::BlockComment);
+        let leading: Vec<TriviaKind> = trivia[1].leading.iter().map(|t| t.kind).collect();
+        assert_eq!(
+            leading,
+            vec![
+                TriviaKind::Newline,
+                TriviaKind::BlockComment,
+                TriviaKind::Whitespace
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_starting_at_line() {
+        let tokens: Vec<Token> = Lexer::starting_at_line("a\n  b", 3)
+            .map(|token| token.unwrap())
+            .collect();
+
+        assert_eq!(tokens[0].span, Span::new(0, 1, 3, 1));
+        assert_eq!(tokens[1].span, Span::new(4, 5, 4, 3));
+    }
+
+    #[test]
+    fn test_scan_tokens_drops_trivia_by_default() {
+        let mut lexer = Lexer::new(" a  // c\n");
+
+        let (token, trivia, _) = lexer.next_token().unwrap();
+
+        assert_eq!(trivia, TokenTrivia::default());
+        assert_eq!(token.lexeme, "a");
+    }
 }
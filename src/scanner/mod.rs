@@ -1,3 +1,5 @@
+pub mod error;
 pub mod lexer;
+pub mod span;
 pub mod token;
 pub mod token_type;
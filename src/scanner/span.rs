@@ -0,0 +1,40 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    // byte offset of the first character
+    pub start: usize,
+    // byte offset one past the last character
+    pub end: usize,
+    // 1-based line of the first character
+    pub line: usize,
+    // 1-based column (in characters) of the first character
+    pub column: usize,
+}
+
+impl Span {
+    pub const fn new(start: usize, end: usize, line: usize, column: usize) -> Span {
+        Span {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+
+    // returns a span that starts where `self` starts and ends where `other` ends
+    pub fn to(&self, other: Span) -> Span {
+        Span {
+            start: self.start,
+            end: other.end,
+            line: self.line,
+            column: self.column,
+        }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
@@ -1,18 +1,95 @@
+use std::borrow::Cow;
+
+use crate::scanner::span::Span;
 use crate::scanner::token_type::TokenType;
 
+// tokens borrow their text from the source they were scanned from
 #[derive(Debug, Clone, PartialEq)]
-pub struct Token {
+pub struct Token<'src> {
     // the enumerated type of token
     pub token_type: TokenType,
+    // the exact source text of the token
+    pub lexeme: &'src str,
     // the value containted in the token if it has one (i.e. String or Number literals)
-    pub literal: Option<Literal>,
-    // the line where the token is found
-    // pub line: i32,
+    pub literal: Option<Literal<'src>>,
+    // where the token is found in the source
+    pub span: Span,
+}
+
+impl<'src> Token<'src> {
+    pub const fn new(
+        token_type: TokenType,
+        lexeme: &'src str,
+        literal: Option<Literal<'src>>,
+        span: Span,
+    ) -> Token<'src> {
+        Token {
+            token_type,
+            lexeme,
+            literal,
+            span,
+        }
+    }
+
+    // the name of an identifier token, empty for any other token
+    pub fn name(&self) -> &str {
+        match &self.literal {
+            Some(Literal::Identifier(name)) => name,
+            _ => "",
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
-pub enum Literal {
-    String(String),
+pub enum Literal<'src> {
+    // the contents of a string literal without the quotes and with escape sequences
+    // decoded, only copied out of the source when it contains escapes
+    String(Cow<'src, str>),
     Numeric(f64),
-    Identifier(String),
+    // the NFC normalized name, only copied out of the source when it wasn't normalized
+    Identifier(Cow<'src, str>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TriviaKind {
+    // a run of whitespace other than line breaks
+    Whitespace,
+    Newline,
+    LineComment,
+    // a `/* */` comment, which may span several lines
+    BlockComment,
+}
+
+// source text that carries no meaning for the parser but is needed to reproduce
+// the source exactly
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trivia<'src> {
+    pub kind: TriviaKind,
+    pub text: &'src str,
+    pub span: Span,
+}
+
+// the whitespace and comments around one token, only collected in lossless mode.
+// It is kept out of `Token` so that tokens stay cheap to clone into the tree.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TokenTrivia<'src> {
+    // whitespace and comments before the token
+    pub leading: Vec<Trivia<'src>>,
+    // whitespace and comments after the token up to the end of its line
+    pub trailing: Vec<Trivia<'src>>,
+}
+
+// rebuilds the source from a lossless token stream and the trivia of each token
+pub fn lossless_source(tokens: &[Token], trivia: &[TokenTrivia]) -> String {
+    let mut source = String::new();
+    for (token, trivia) in tokens.iter().zip(trivia) {
+        for leading in trivia.leading.iter() {
+            source.push_str(leading.text);
+        }
+        source.push_str(token.lexeme);
+        for trailing in trivia.trailing.iter() {
+            source.push_str(trailing.text);
+        }
+    }
+    source
 }
@@ -1,4 +1,4 @@
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum TokenType {
     // Single-character tokens.
     LeftParen,
@@ -43,5 +43,7 @@ pub enum TokenType {
     False,
     Var,
     While,
+    // Placeholder for source that could not be scanned
+    Error,
     Eof,
 }
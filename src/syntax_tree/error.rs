@@ -0,0 +1,69 @@
+use crate::diagnostic::Diagnostic;
+use crate::diagnostic::ErrorKind;
+use crate::scanner::token_type::TokenType;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseErrorKind {
+    // no expression could be started at the current token
+    ExpectedExpression,
+    // a specific token was required but something else was found
+    ExpectedToken(Expected),
+    // the left-hand side of `=` is not something that can be assigned to
+    InvalidAssignmentTarget,
+    // a call or function declaration exceeds the maximum number of arguments
+    TooManyArguments,
+    // expressions are nested inside one another beyond the parser's limit
+    TooDeeplyNested,
+}
+
+impl ErrorKind for ParseErrorKind {
+    // missing tokens get a code per token, whatever construct they were expected in
+    fn code(&self) -> &'static str {
+        match self {
+            ParseErrorKind::ExpectedExpression => "P0001",
+            ParseErrorKind::ExpectedToken(Expected::Semicolon) => "P0002",
+            ParseErrorKind::ExpectedToken(Expected::RightParen) => "P0003",
+            ParseErrorKind::ExpectedToken(Expected::LeftParen) => "P0004",
+            ParseErrorKind::ExpectedToken(Expected::RightBrace) => "P0005",
+            ParseErrorKind::ExpectedToken(Expected::LeftBrace) => "P0006",
+            ParseErrorKind::ExpectedToken(Expected::Identifier) => "P0007",
+            ParseErrorKind::ExpectedToken(Expected::Dot) => "P0008",
+            ParseErrorKind::ExpectedToken(Expected::Eof) => "P0009",
+            ParseErrorKind::InvalidAssignmentTarget => "P0010",
+            ParseErrorKind::TooManyArguments => "P0011",
+            ParseErrorKind::TooDeeplyNested => "P0012",
+        }
+    }
+}
+
+// the tokens the parser can require. Each has its own error code, so expecting a
+// new kind of token means adding it here along with a code and an explanation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Expected {
+    Semicolon,
+    RightParen,
+    LeftParen,
+    RightBrace,
+    LeftBrace,
+    Identifier,
+    Dot,
+    Eof,
+}
+
+impl Expected {
+    pub fn token_type(self) -> TokenType {
+        match self {
+            Expected::Semicolon => TokenType::Semicolon,
+            Expected::RightParen => TokenType::RightParen,
+            Expected::LeftParen => TokenType::LeftParen,
+            Expected::RightBrace => TokenType::RightBrace,
+            Expected::LeftBrace => TokenType::LeftBrace,
+            Expected::Identifier => TokenType::Identifier,
+            Expected::Dot => TokenType::Dot,
+            Expected::Eof => TokenType::Eof,
+        }
+    }
+}
+
+// a syntax error, pointing at the token at which the parser gave up
+pub type ParseError = Diagnostic<ParseErrorKind>;
@@ -0,0 +1,646 @@
+use crate::scanner::token::Token;
+use crate::scanner::token::TokenTrivia;
+use crate::scanner::token::Trivia;
+use crate::scanner::token::TriviaKind;
+use crate::scanner::token_type::TokenType;
+use crate::syntax_tree::expression::Expression;
+use crate::syntax_tree::statement::FunctionDecl;
+use crate::syntax_tree::statement::Stmt;
+
+const INDENT: &str = "    ";
+// argument lists of calls reaching past this column are split one per line
+const MAX_WIDTH: usize = 80;
+
+// prints a parsed program back as canonically formatted source.
+//
+// the tree drops comments and punctuation, so the formatter walks the lossless
+// tokens the program was parsed from alongside it: every token it prints is the
+// next one in the source, which is where the comments around it come from. The
+// printed tokens are exactly the source tokens, so the output parses to the same
+// tree.
+pub struct Formatter<'a, 'src> {
+    tokens: &'a [Token<'src>],
+    // the comments around each token
+    trivia: &'a [TokenTrivia<'src>],
+    current: usize,
+    output: String,
+    indent: usize,
+    // a space goes before the next token unless it starts a line
+    space: bool,
+    // a line comment was printed, so the next token has to start a new line
+    line_break: bool,
+    // trying whether a call fits on one line, so nested calls aren't split
+    flat: bool,
+}
+
+impl<'a, 'src> Formatter<'a, 'src> {
+    // `tokens` and `trivia` must be the output of `scan_tokens_lossless` that
+    // `statements` were parsed from
+    pub fn format(
+        statements: &[Stmt<'src>],
+        tokens: &'a [Token<'src>],
+        trivia: &'a [TokenTrivia<'src>],
+    ) -> String {
+        let mut formatter = Formatter {
+            tokens,
+            trivia,
+            current: 0,
+            output: String::new(),
+            indent: 0,
+            space: false,
+            line_break: false,
+            flat: false,
+        };
+        for stmt in statements {
+            formatter.statement(stmt);
+            formatter.newline();
+        }
+        // comments after the last statement
+        formatter.expect(TokenType::Eof);
+        formatter.comments(&trivia[formatter.current].leading, false);
+
+        let mut output = formatter.output.trim_end().to_string();
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        output
+    }
+
+    fn statement(&mut self, stmt: &Stmt<'src>) {
+        if self.check(TokenType::For) {
+            return self.for_statement(stmt);
+        }
+        match stmt {
+            Stmt::Expression(expr) => {
+                self.expression(expr);
+                self.token(TokenType::Semicolon);
+            }
+            Stmt::Print(expr) => {
+                self.token(TokenType::Print);
+                self.space = true;
+                self.expression(expr);
+                self.token(TokenType::Semicolon);
+            }
+            Stmt::Var { initializer, .. } => {
+                self.token(TokenType::Var);
+                self.space = true;
+                self.token(TokenType::Identifier);
+                if let Some(initializer) = initializer {
+                    self.space = true;
+                    self.token(TokenType::Equal);
+                    self.space = true;
+                    self.expression(initializer);
+                }
+                self.token(TokenType::Semicolon);
+            }
+            Stmt::Return { value, .. } => {
+                self.token(TokenType::Return);
+                if let Some(value) = value {
+                    self.space = true;
+                    self.expression(value);
+                }
+                self.token(TokenType::Semicolon);
+            }
+            Stmt::Block(statements) => self.block(statements),
+            Stmt::Function(decl) => {
+                self.token(TokenType::Fun);
+                self.space = true;
+                self.function(decl);
+            }
+            Stmt::Class {
+                superclass,
+                methods,
+                ..
+            } => {
+                self.token(TokenType::Class);
+                self.space = true;
+                self.token(TokenType::Identifier);
+                if let Some(superclass) = superclass {
+                    self.space = true;
+                    self.token(TokenType::Less);
+                    self.space = true;
+                    self.expression(superclass);
+                }
+                self.space = true;
+                self.token(TokenType::LeftBrace);
+                if methods.is_empty() && self.closes_right_away() {
+                    return self.token(TokenType::RightBrace);
+                }
+                self.indent += 1;
+                self.newline();
+                for method in methods {
+                    self.function(method);
+                    self.newline();
+                }
+                self.close(TokenType::RightBrace);
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.token(TokenType::If);
+                self.space = true;
+                self.condition(condition);
+                let then_block = self.check(TokenType::LeftBrace);
+                self.body(then_branch);
+                if let Some(else_branch) = else_branch {
+                    if then_block {
+                        self.space = true;
+                    } else {
+                        self.newline();
+                    }
+                    self.token(TokenType::Else);
+                    if matches!(**else_branch, Stmt::If { .. }) {
+                        self.space = true;
+                        self.statement(else_branch);
+                    } else {
+                        self.body(else_branch);
+                    }
+                }
+            }
+            Stmt::While { condition, body } => {
+                self.token(TokenType::While);
+                self.space = true;
+                self.condition(condition);
+                self.body(body);
+            }
+        }
+    }
+
+    // the parser desugars `for` loops into `while` loops, the source tokens tell
+    // which clauses were written
+    fn for_statement(&mut self, stmt: &Stmt<'src>) {
+        self.token(TokenType::For);
+        self.space = true;
+        self.token(TokenType::LeftParen);
+
+        let mut stmt = stmt;
+        if self.check(TokenType::Semicolon) {
+            self.token(TokenType::Semicolon);
+        } else {
+            let Stmt::Block(statements) = stmt else {
+                unreachable!("a for loop with an initializer is wrapped in a block")
+            };
+            self.statement(&statements[0]);
+            stmt = &statements[1];
+        }
+
+        let Stmt::While { condition, body } = stmt else {
+            unreachable!("a for loop is desugared into a while loop")
+        };
+        if !self.check(TokenType::Semicolon) {
+            self.space = true;
+            self.expression(condition);
+        }
+        self.token(TokenType::Semicolon);
+
+        let mut body = &**body;
+        if !self.check(TokenType::RightParen) {
+            let Stmt::Block(statements) = body else {
+                unreachable!("the increment is appended to the body in a block")
+            };
+            let Stmt::Expression(increment) = &statements[1] else {
+                unreachable!("the increment is an expression statement")
+            };
+            self.space = true;
+            self.expression(increment);
+            body = &statements[0];
+        }
+        self.token(TokenType::RightParen);
+        self.body(body);
+    }
+
+    fn condition(&mut self, condition: &Expression<'src>) {
+        self.token(TokenType::LeftParen);
+        self.expression(condition);
+        self.token(TokenType::RightParen);
+    }
+
+    // blocks open on the line of their statement, anything else goes indented on
+    // the next line
+    fn body(&mut self, body: &Stmt<'src>) {
+        if self.check(TokenType::LeftBrace) {
+            self.space = true;
+            self.statement(body);
+        } else {
+            self.indent += 1;
+            self.newline();
+            self.statement(body);
+            self.indent -= 1;
+        }
+    }
+
+    fn block(&mut self, statements: &[Stmt<'src>]) {
+        self.token(TokenType::LeftBrace);
+        if statements.is_empty() && self.closes_right_away() {
+            return self.token(TokenType::RightBrace);
+        }
+        self.indent += 1;
+        self.newline();
+        for stmt in statements {
+            self.statement(stmt);
+            self.newline();
+        }
+        self.close(TokenType::RightBrace);
+    }
+
+    // the name, parameters and body of a function or method
+    fn function(&mut self, decl: &FunctionDecl<'src>) {
+        self.token(TokenType::Identifier);
+        self.token(TokenType::LeftParen);
+        for (i, _) in decl.params.iter().enumerate() {
+            if i > 0 {
+                self.token(TokenType::Comma);
+                self.space = true;
+            }
+            self.token(TokenType::Identifier);
+        }
+        self.token(TokenType::RightParen);
+        self.space = true;
+        self.block(&decl.body);
+    }
+
+    fn expression(&mut self, expr: &Expression<'src>) {
+        match expr {
+            Expression::Unary {
+                operator, right, ..
+            } => {
+                self.token(operator.token_type);
+                self.expression(right);
+            }
+            Expression::Binary {
+                left,
+                operator,
+                right,
+                ..
+            }
+            | Expression::Logical {
+                left,
+                operator,
+                right,
+                ..
+            } => {
+                self.expression(left);
+                self.space = true;
+                self.token(operator.token_type);
+                self.space = true;
+                self.expression(right);
+            }
+            Expression::Literal(token) => self.token(token.token_type),
+            Expression::Grouping { expression, .. } => {
+                self.token(TokenType::LeftParen);
+                self.expression(expression);
+                self.token(TokenType::RightParen);
+            }
+            Expression::Call {
+                callee, arguments, ..
+            } => {
+                self.expression(callee);
+                self.token(TokenType::LeftParen);
+                self.arguments(arguments);
+            }
+            Expression::Get { object, .. } => {
+                self.expression(object);
+                self.dot_after(object);
+                self.token(TokenType::Identifier);
+            }
+            Expression::Set { object, value, .. } => {
+                self.expression(object);
+                self.dot_after(object);
+                self.token(TokenType::Identifier);
+                self.space = true;
+                self.token(TokenType::Equal);
+                self.space = true;
+                self.expression(value);
+            }
+            Expression::Super { .. } => {
+                self.token(TokenType::Super);
+                self.token(TokenType::Dot);
+                self.token(TokenType::Identifier);
+            }
+            Expression::This { .. } => self.token(TokenType::This),
+            Expression::Variable { .. } => self.token(TokenType::Identifier),
+            Expression::Assign { value, .. } => {
+                self.token(TokenType::Identifier);
+                self.space = true;
+                self.token(TokenType::Equal);
+                self.space = true;
+                self.expression(value);
+            }
+        }
+    }
+
+    // prints the dot of a property access. A number right before it would take
+    // the dot as its decimal point, so the two are kept apart.
+    fn dot_after(&mut self, object: &Expression<'src>) {
+        if matches!(object, Expression::Literal(token) if token.token_type == TokenType::Number) {
+            self.space = true;
+        }
+        self.token(TokenType::Dot);
+    }
+
+    // the arguments of a call and its closing parenthesis, on one line if they fit
+    // and one per line otherwise
+    fn arguments(&mut self, arguments: &[Expression<'src>]) {
+        if arguments.is_empty() || self.flat {
+            return self.flat_arguments(arguments);
+        }
+
+        let (length, current) = (self.output.len(), self.current);
+        self.flat = true;
+        self.flat_arguments(arguments);
+        self.flat = false;
+        let line = &self.output[length..];
+        if !line.contains('\n') && !self.line_break && self.column() <= MAX_WIDTH {
+            return;
+        }
+
+        self.output.truncate(length);
+        self.current = current;
+        self.space = false;
+        self.line_break = false;
+        self.indent += 1;
+        self.newline();
+        for (i, argument) in arguments.iter().enumerate() {
+            if i > 0 {
+                self.token(TokenType::Comma);
+                self.newline();
+            }
+            self.expression(argument);
+        }
+        self.newline();
+        self.close(TokenType::RightParen);
+    }
+
+    fn flat_arguments(&mut self, arguments: &[Expression<'src>]) {
+        for (i, argument) in arguments.iter().enumerate() {
+            if i > 0 {
+                self.token(TokenType::Comma);
+                self.space = true;
+            }
+            self.expression(argument);
+        }
+        self.token(TokenType::RightParen);
+    }
+
+    // whether the next token closes the brace just printed, with no comments inside
+    fn closes_right_away(&self) -> bool {
+        self.check(TokenType::RightBrace)
+            && !self.line_break
+            && !self.trivia[self.current].leading.iter().any(is_comment)
+    }
+
+    fn check(&self, token_type: TokenType) -> bool {
+        self.tokens
+            .get(self.current)
+            .is_some_and(|token| token.token_type == token_type)
+    }
+
+    // printing anything but the next source token would change the program
+    fn expect(&self, token_type: TokenType) {
+        assert!(
+            self.check(token_type),
+            "the formatter expected {:?} but the next source token is {:?}",
+            token_type,
+            self.tokens.get(self.current).map(|token| token.token_type)
+        );
+    }
+
+    // prints the next source token with its comments
+    fn token(&mut self, token_type: TokenType) {
+        self.expect(token_type);
+        let trivia = self.trivia;
+        self.comments(&trivia[self.current].leading, true);
+        self.finish_token();
+    }
+
+    // prints the token closing an indented block, keeping the comments before it
+    // inside the block
+    fn close(&mut self, token_type: TokenType) {
+        self.expect(token_type);
+        let trivia = self.trivia;
+        self.comments(&trivia[self.current].leading, false);
+        self.indent -= 1;
+        self.finish_token();
+    }
+
+    fn finish_token(&mut self) {
+        self.write(self.tokens[self.current].lexeme);
+        let trailing = &self.trivia[self.current].trailing;
+        self.current += 1;
+        for trivia in trailing.iter().filter(|t| is_comment(t)) {
+            self.space = true;
+            self.write(trivia.text.trim_end());
+            if trivia.kind == TriviaKind::LineComment {
+                self.line_break = true;
+            } else {
+                self.space = true;
+            }
+        }
+    }
+
+    // prints the comments before a token. Comments that started a line keep doing
+    // so, and a single blank line is kept wherever the source had any.
+    fn comments(&mut self, trivia: &[Trivia<'src>], keep_blank_line: bool) {
+        let mut newlines = 0;
+        for (i, item) in trivia.iter().enumerate() {
+            match item.kind {
+                TriviaKind::Newline => newlines += 1,
+                TriviaKind::Whitespace => {}
+                TriviaKind::LineComment | TriviaKind::BlockComment => {
+                    let line_comment = item.kind == TriviaKind::LineComment;
+                    if self.at_line_start() {
+                        if newlines > 1 {
+                            self.blank_line();
+                        }
+                        self.write(item.text.trim_end());
+                        let own_line = trivia[i + 1..]
+                            .iter()
+                            .take_while(|t| !is_comment(t))
+                            .any(|t| t.kind == TriviaKind::Newline);
+                        if line_comment || own_line {
+                            self.newline();
+                        } else {
+                            self.space = true;
+                        }
+                    } else {
+                        self.space = true;
+                        self.write(item.text.trim_end());
+                        if line_comment {
+                            self.line_break = true;
+                        } else {
+                            self.space = true;
+                        }
+                    }
+                    newlines = 0;
+                }
+            }
+        }
+        if newlines > 1 && keep_blank_line && self.at_line_start() {
+            self.blank_line();
+        }
+    }
+
+    fn write(&mut self, text: &str) {
+        if self.line_break {
+            // the rest of a line cut short by a comment is indented one more level
+            self.newline();
+            self.output.push_str(&INDENT.repeat(self.indent + 1));
+        } else if self.at_line_start() {
+            self.output.push_str(&INDENT.repeat(self.indent));
+        } else if self.space {
+            self.output.push(' ');
+        }
+        self.space = false;
+        self.output.push_str(text);
+    }
+
+    fn newline(&mut self) {
+        self.output.push('\n');
+        self.space = false;
+        self.line_break = false;
+    }
+
+    // blank lines are dropped at the start of the file and right after an opening
+    // brace or parenthesis
+    fn blank_line(&mut self) {
+        if self.output.ends_with('\n')
+            && !self.output.ends_with("\n\n")
+            && !self.output.ends_with("{\n")
+            && !self.output.ends_with("(\n")
+        {
+            self.output.push('\n');
+        }
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.output.is_empty() || self.output.ends_with('\n')
+    }
+
+    // the width of the line printed so far
+    fn column(&self) -> usize {
+        let line_start = self.output.rfind('\n').map_or(0, |i| i + 1);
+        self.output[line_start..].chars().count()
+    }
+}
+
+fn is_comment(trivia: &Trivia) -> bool {
+    matches!(
+        trivia.kind,
+        TriviaKind::LineComment | TriviaKind::BlockComment
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::lexer::scan_tokens_lossless;
+    use crate::syntax_tree::parser::Parser;
+    use crate::syntax_tree::printer::AstPrinter;
+
+    fn format(source: &str) -> String {
+        let (tokens, trivia, diagnostics) = scan_tokens_lossless(source);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        let (statements, errors) = Parser::new(tokens.clone()).parse();
+        assert!(errors.is_empty(), "{:?}", errors);
+        Formatter::format(&statements, &tokens, &trivia)
+    }
+
+    fn tree(source: &str) -> String {
+        let (tokens, _, _) = scan_tokens_lossless(source);
+        AstPrinter::print(&Parser::new(tokens).parse().0)
+    }
+
+    // formats the source, checking the result is stable and parses to the same tree
+    fn assert_formats(source: &str, expected: &str) {
+        let formatted = format(source);
+        assert_eq!(formatted, expected);
+        assert_eq!(format(&formatted), formatted);
+        assert_eq!(tree(&formatted), tree(source));
+    }
+
+    #[test]
+    fn test_format_spacing_and_indentation() {
+        assert_formats(
+            "var a=1+2*-3;\nfun add(a,b){return a+b;}\nif(a>1)print a;else{print !a;}",
+            "var a = 1 + 2 * -3;\n\
+             fun add(a, b) {\n    return a + b;\n}\n\
+             if (a > 1)\n    print a;\nelse {\n    print !a;\n}\n",
+        );
+    }
+
+    #[test]
+    fn test_format_control_flow() {
+        assert_formats(
+            "while(i<3){i=i+1;}\nif (a) { a; } else if (b) { b; } else c;",
+            "while (i < 3) {\n    i = i + 1;\n}\n\
+             if (a) {\n    a;\n} else if (b) {\n    b;\n} else\n    c;\n",
+        );
+    }
+
+    #[test]
+    fn test_format_keeps_for_loops() {
+        assert_formats(
+            "for(var i=0;i<3;i=i+1)print i;\nfor(;;){}\nfor(i=0;;)print i;\nfor(;i<3;)i=i+1;",
+            "for (var i = 0; i < 3; i = i + 1)\n    print i;\n\
+             for (;;) {}\n\
+             for (i = 0;;)\n    print i;\n\
+             for (; i < 3;)\n    i = i + 1;\n",
+        );
+    }
+
+    #[test]
+    fn test_format_classes() {
+        assert_formats(
+            "class A{}\nclass B<A{init(x){this.x=x;}get(){return super.get()+this.x;}}",
+            "class A {}\n\
+             class B < A {\n    init(x) {\n        this.x = x;\n    }\n    \
+             get() {\n        return super.get() + this.x;\n    }\n}\n",
+        );
+    }
+
+    #[test]
+    fn test_format_keeps_numbers_apart_from_property_dots() {
+        assert_formats(
+            "print 1 .x;\n1 .x = 2.5.y;\n",
+            "print 1 .x;\n1 .x = 2.5 .y;\n",
+        );
+    }
+
+    #[test]
+    fn test_format_keeps_comments_and_blank_lines() {
+        assert_formats(
+            "// header\n\n\n\nvar a = 1; // one\n/* two */ var b\n  = a   /* inline */ + 1;\n\n{\n  // inside\n  print a;\n  // last\n}\n// trailing\n",
+            "// header\n\n\
+             var a = 1; // one\n\
+             /* two */ var b = a /* inline */ + 1;\n\n\
+             {\n    // inside\n    print a;\n    // last\n}\n\
+             // trailing\n",
+        );
+    }
+
+    #[test]
+    fn test_format_breaks_after_line_comments_in_expressions() {
+        assert_formats("var a = 1 + // one\n2;", "var a = 1 + // one\n    2;\n");
+    }
+
+    #[test]
+    fn test_format_wraps_long_argument_lists() {
+        assert_formats(
+            "print some_function(first_argument, second_argument, third_argument, fourth_argument);",
+            "print some_function(\n    first_argument,\n    second_argument,\n    third_argument,\n    fourth_argument\n);\n",
+        );
+        assert_formats(
+            "outer(inner(a, b), c);\nf(a, // first\nb);",
+            "outer(inner(a, b), c);\nf(\n    a, // first\n    b\n);\n",
+        );
+    }
+
+    #[test]
+    fn test_format_is_unchanged_when_formatted() {
+        let source = "fun f() {}\n\nprint f();\n";
+        assert_eq!(format(source), source);
+        assert_eq!(format(""), "");
+    }
+}
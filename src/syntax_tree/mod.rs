@@ -1,2 +1,7 @@
+pub mod error;
 pub mod expression;
+pub mod formatter;
 pub mod parser;
+pub mod printer;
+pub mod statement;
+pub mod visitor;
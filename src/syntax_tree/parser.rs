@@ -1 +1,1295 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::vec;
 
+use crate::scanner::span::Span;
+use crate::scanner::token::Token;
+use crate::scanner::token_type::TokenType;
+use crate::syntax_tree::error::Expected;
+use crate::syntax_tree::error::ParseError;
+use crate::syntax_tree::error::ParseErrorKind;
+use crate::syntax_tree::expression::Expression;
+use crate::syntax_tree::statement::FunctionDecl;
+use crate::syntax_tree::statement::Stmt;
+
+// the most arguments a call (or parameters a function) may have
+pub const MAX_ARGUMENTS: usize = 255;
+
+// how deeply expressions may nest. The parser, resolver and interpreter all recurse
+// over nested expressions, so without a limit deep enough input overflows the stack.
+pub const MAX_NESTING_DEPTH: usize = 256;
+
+// pulls tokens from any iterator, like a `Vec<Token>` or a `Lexer`, one at a time
+// as the grammar asks for them, so the source doesn't have to be scanned up front
+pub struct Parser<'src, I> {
+    tokens: I,
+    // the token being looked at, which stays EOF once the tokens run out
+    current: Token<'src>,
+    // the token consumed last
+    previous: Token<'src>,
+    // errors that were reported without unwinding the parser
+    errors: Vec<ParseError>,
+    // how many expressions are being parsed inside one another
+    depth: usize,
+}
+
+impl<'src, I: Iterator<Item = Token<'src>>> Parser<'src, I> {
+    pub fn new<T: IntoIterator<IntoIter = I>>(tokens: T) -> Parser<'src, I> {
+        let eof = Token::new(TokenType::Eof, "", None, Span::default());
+        let mut parser = Parser {
+            tokens: tokens.into_iter(),
+            current: eof.clone(),
+            previous: eof,
+            errors: Vec::new(),
+            depth: 0,
+        };
+        parser.current = parser.next_token();
+        parser.previous = parser.current.clone();
+        parser
+    }
+
+    // parses a whole program. Syntax errors don't stop the parser: it records them,
+    // skips to the next statement boundary and carries on, returning whatever could
+    // be parsed alongside every error encountered.
+    pub fn parse(&mut self) -> (Vec<Stmt<'src>>, Vec<ParseError>) {
+        let mut statements: Vec<Stmt<'src>> = Vec::new();
+
+        while !self.is_at_end() {
+            match self.declaration() {
+                Ok(stmt) => statements.push(stmt),
+                Err(err) => {
+                    self.errors.push(err);
+                    self.synchronize();
+                }
+            }
+        }
+        (statements, std::mem::take(&mut self.errors))
+    }
+
+    // parses input consisting of exactly one expression, as typed at the REPL
+    pub fn parse_expression(&mut self) -> Result<Expression<'src>, ParseError> {
+        let expr = self.expression()?;
+        // errors the parser recovered from still make the expression invalid
+        if !self.errors.is_empty() {
+            return Err(self.errors.remove(0));
+        }
+        if !self.is_at_end() {
+            return Err(ParseError::new(
+                ParseErrorKind::ExpectedToken(Expected::Eof),
+                self.peek().span,
+                String::from("Expect end of input."),
+            ));
+        }
+        Ok(expr)
+    }
+
+    fn declaration(&mut self) -> Result<Stmt<'src>, ParseError> {
+        if self.is_matching(&[TokenType::Class]) {
+            return self.class_declaration();
+        }
+        if self.is_matching(&[TokenType::Fun]) {
+            return Ok(Stmt::Function(Rc::new(self.function("function")?)));
+        }
+        if self.is_matching(&[TokenType::Var]) {
+            return self.var_declaration();
+        }
+        self.statement()
+    }
+
+    fn class_declaration(&mut self) -> Result<Stmt<'src>, ParseError> {
+        let name = self.consume(Expected::Identifier, "Expect class name.")?;
+
+        let superclass = if self.is_matching(&[TokenType::Less]) {
+            let superclass_name = self.consume(Expected::Identifier, "Expect superclass name.")?;
+            Some(Expression::variable(superclass_name))
+        } else {
+            None
+        };
+
+        let left_brace = self
+            .consume(Expected::LeftBrace, "Expect '{' before class body.")?
+            .span;
+
+        let mut methods: Vec<Rc<FunctionDecl<'src>>> = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            methods.push(Rc::new(self.function("method")?));
+        }
+
+        self.consume_closing(
+            Expected::RightBrace,
+            "Expect '}' after class body.",
+            left_brace,
+            "class body opened here",
+        )?;
+        Ok(Stmt::Class {
+            name,
+            superclass,
+            methods,
+        })
+    }
+
+    // parses the name, parameters and body of a function. `kind` names what is
+    // being declared in error messages.
+    fn function(&mut self, kind: &str) -> Result<FunctionDecl<'src>, ParseError> {
+        let name = self.consume(Expected::Identifier, &format!("Expect {} name.", kind))?;
+        let left_paren = self
+            .consume(
+                Expected::LeftParen,
+                &format!("Expect '(' after {} name.", kind),
+            )?
+            .span;
+
+        let mut params: Vec<Token<'src>> = Vec::new();
+        if !self.check(TokenType::RightParen) {
+            loop {
+                if params.len() >= MAX_ARGUMENTS {
+                    self.errors.push(ParseError::new(
+                        ParseErrorKind::TooManyArguments,
+                        self.peek().span,
+                        format!("Can't have more than {} parameters.", MAX_ARGUMENTS),
+                    ));
+                }
+                params.push(self.consume(Expected::Identifier, "Expect parameter name.")?);
+                if !self.is_matching(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+        self.consume_closing(
+            Expected::RightParen,
+            "Expect ')' after parameters.",
+            left_paren,
+            "opening parenthesis here",
+        )?;
+
+        self.consume(
+            Expected::LeftBrace,
+            &format!("Expect '{{' before {} body.", kind),
+        )?;
+        let body = self.block()?;
+
+        Ok(FunctionDecl { name, params, body })
+    }
+
+    fn var_declaration(&mut self) -> Result<Stmt<'src>, ParseError> {
+        let name = self.consume(Expected::Identifier, "Expect variable name.")?;
+
+        let initializer = if self.is_matching(&[TokenType::Equal]) {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+
+        self.consume(
+            Expected::Semicolon,
+            "Expect ';' after variable declaration.",
+        )?;
+        Ok(Stmt::Var { name, initializer })
+    }
+
+    fn statement(&mut self) -> Result<Stmt<'src>, ParseError> {
+        if self.is_matching(&[TokenType::For]) {
+            return self.for_statement();
+        }
+        if self.is_matching(&[TokenType::If]) {
+            return self.if_statement();
+        }
+        if self.is_matching(&[TokenType::While]) {
+            return self.while_statement();
+        }
+        if self.is_matching(&[TokenType::Print]) {
+            return self.print_statement();
+        }
+        if self.is_matching(&[TokenType::Return]) {
+            return self.return_statement();
+        }
+        if self.is_matching(&[TokenType::LeftBrace]) {
+            return Ok(Stmt::Block(self.block()?));
+        }
+        self.expression_statement()
+    }
+
+    fn block(&mut self) -> Result<Vec<Stmt<'src>>, ParseError> {
+        let left_brace = self.previous().span;
+        let mut statements: Vec<Stmt<'src>> = Vec::new();
+
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            statements.push(self.declaration()?);
+        }
+
+        self.consume_closing(
+            Expected::RightBrace,
+            "Expect '}' after block.",
+            left_brace,
+            "block opened here",
+        )?;
+        Ok(statements)
+    }
+
+    // desugars `for (init; cond; incr) body` into `{ init; while (cond) { body; incr; } }`
+    fn for_statement(&mut self) -> Result<Stmt<'src>, ParseError> {
+        let for_keyword = self.previous().span;
+        let left_paren = self
+            .consume(Expected::LeftParen, "Expect '(' after 'for'.")?
+            .span;
+
+        let initializer = if self.is_matching(&[TokenType::Semicolon]) {
+            None
+        } else if self.is_matching(&[TokenType::Var]) {
+            Some(self.var_declaration()?)
+        } else {
+            Some(self.expression_statement()?)
+        };
+
+        let condition = if self.check(TokenType::Semicolon) {
+            // an omitted condition loops forever
+            let semicolon = self.peek();
+            Expression::Literal(Token::new(
+                TokenType::True,
+                "",
+                None,
+                for_keyword.to(semicolon.span),
+            ))
+        } else {
+            self.expression()?
+        };
+        self.consume(Expected::Semicolon, "Expect ';' after loop condition.")?;
+
+        let increment = if self.check(TokenType::RightParen) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume_closing(
+            Expected::RightParen,
+            "Expect ')' after for clauses.",
+            left_paren,
+            "opening parenthesis here",
+        )?;
+
+        let mut body = self.statement()?;
+
+        if let Some(increment) = increment {
+            body = Stmt::Block(vec![body, Stmt::Expression(increment)]);
+        }
+        body = Stmt::While {
+            condition,
+            body: Box::new(body),
+        };
+        if let Some(initializer) = initializer {
+            body = Stmt::Block(vec![initializer, body]);
+        }
+        Ok(body)
+    }
+
+    fn if_statement(&mut self) -> Result<Stmt<'src>, ParseError> {
+        let left_paren = self
+            .consume(Expected::LeftParen, "Expect '(' after 'if'.")?
+            .span;
+        let condition = self.expression()?;
+        self.consume_closing(
+            Expected::RightParen,
+            "Expect ')' after if condition.",
+            left_paren,
+            "opening parenthesis here",
+        )?;
+
+        let then_branch = Box::new(self.statement()?);
+        // a dangling `else` binds to the nearest `if`
+        let else_branch = if self.is_matching(&[TokenType::Else]) {
+            Some(Box::new(self.statement()?))
+        } else {
+            None
+        };
+
+        Ok(Stmt::If {
+            condition,
+            then_branch,
+            else_branch,
+        })
+    }
+
+    fn while_statement(&mut self) -> Result<Stmt<'src>, ParseError> {
+        let left_paren = self
+            .consume(Expected::LeftParen, "Expect '(' after 'while'.")?
+            .span;
+        let condition = self.expression()?;
+        self.consume_closing(
+            Expected::RightParen,
+            "Expect ')' after condition.",
+            left_paren,
+            "opening parenthesis here",
+        )?;
+        let body = Box::new(self.statement()?);
+
+        Ok(Stmt::While { condition, body })
+    }
+
+    fn print_statement(&mut self) -> Result<Stmt<'src>, ParseError> {
+        let value = self.expression()?;
+        self.consume(Expected::Semicolon, "Expect ';' after value.")?;
+        Ok(Stmt::Print(value))
+    }
+
+    fn return_statement(&mut self) -> Result<Stmt<'src>, ParseError> {
+        let keyword = self.previous().clone();
+        let value = if self.check(TokenType::Semicolon) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+
+        self.consume(Expected::Semicolon, "Expect ';' after return value.")?;
+        Ok(Stmt::Return { keyword, value })
+    }
+
+    fn expression_statement(&mut self) -> Result<Stmt<'src>, ParseError> {
+        let expr = self.expression()?;
+        self.consume(Expected::Semicolon, "Expect ';' after expression.")?;
+        Ok(Stmt::Expression(expr))
+    }
+
+    pub fn expression(&mut self) -> Result<Expression<'src>, ParseError> {
+        self.parse_precedence(Precedence::Assignment)
+    }
+
+    // parses an expression whose operators all bind at least as tightly as `precedence`
+    fn parse_precedence(&mut self, precedence: Precedence) -> Result<Expression<'src>, ParseError> {
+        if self.depth >= MAX_NESTING_DEPTH {
+            return Err(ParseError::new(
+                ParseErrorKind::TooDeeplyNested,
+                self.peek().span,
+                format!(
+                    "Can't nest expressions more than {} deep.",
+                    MAX_NESTING_DEPTH
+                ),
+            )
+            .with_help("move inner parts of the expression into variables"));
+        }
+        self.depth += 1;
+        let expr = self.nested_expression(precedence);
+        self.depth -= 1;
+        expr
+    }
+
+    fn nested_expression(
+        &mut self,
+        precedence: Precedence,
+    ) -> Result<Expression<'src>, ParseError> {
+        let Some(prefix) = rule(self.peek().token_type).prefix else {
+            return Err(ParseError::new(
+                ParseErrorKind::ExpectedExpression,
+                self.peek().span,
+                String::from("Expect expression."),
+            ));
+        };
+        self.advance();
+        let mut expr = prefix(self)?;
+
+        loop {
+            let rule = rule(self.peek().token_type);
+            let Some(infix) = rule.infix.filter(|_| rule.precedence >= precedence) else {
+                break;
+            };
+            self.advance();
+            expr = infix(self, expr)?;
+        }
+        Ok(expr)
+    }
+
+    fn literal(&mut self) -> Result<Expression<'src>, ParseError> {
+        Ok(Expression::Literal(self.previous().clone()))
+    }
+
+    fn variable(&mut self) -> Result<Expression<'src>, ParseError> {
+        Ok(Expression::variable(self.previous().clone()))
+    }
+
+    fn this(&mut self) -> Result<Expression<'src>, ParseError> {
+        Ok(Expression::This {
+            keyword: self.previous().clone(),
+            depth: Cell::new(None),
+        })
+    }
+
+    fn super_method(&mut self) -> Result<Expression<'src>, ParseError> {
+        let keyword = self.previous().clone();
+        self.consume(Expected::Dot, "Expect '.' after 'super'.")?;
+        let method = self.consume(Expected::Identifier, "Expect superclass method name.")?;
+        let span = keyword.span.to(method.span);
+        Ok(Expression::Super {
+            keyword,
+            method,
+            span,
+            depth: Cell::new(None),
+        })
+    }
+
+    fn grouping(&mut self) -> Result<Expression<'src>, ParseError> {
+        let left_paren = self.previous().span;
+        let expr = self.expression()?;
+        self.consume_closing(
+            Expected::RightParen,
+            "Expect ')' after expression.",
+            left_paren,
+            "opening parenthesis here",
+        )?;
+        Ok(Expression::Grouping {
+            expression: Box::new(expr),
+            span: left_paren.to(self.previous().span),
+        })
+    }
+
+    fn unary(&mut self) -> Result<Expression<'src>, ParseError> {
+        let operator = self.previous().clone();
+        let right = self.parse_precedence(Precedence::Unary)?;
+        let span = operator.span.to(right.span());
+        Ok(Expression::Unary {
+            operator,
+            right: Box::new(right),
+            span,
+        })
+    }
+
+    // binary operators are left-associative, so the right operand only takes
+    // operators that bind tighter
+    fn binary(&mut self, left: Expression<'src>) -> Result<Expression<'src>, ParseError> {
+        let operator = self.previous().clone();
+        let right = self.parse_precedence(rule::<I>(operator.token_type).precedence.next())?;
+        let span = left.span().to(right.span());
+        Ok(Expression::Binary {
+            left: Box::new(left),
+            operator,
+            right: Box::new(right),
+            span,
+        })
+    }
+
+    fn logical(&mut self, left: Expression<'src>) -> Result<Expression<'src>, ParseError> {
+        let operator = self.previous().clone();
+        let right = self.parse_precedence(rule::<I>(operator.token_type).precedence.next())?;
+        let span = left.span().to(right.span());
+        Ok(Expression::Logical {
+            left: Box::new(left),
+            operator,
+            right: Box::new(right),
+            span,
+        })
+    }
+
+    fn assignment(&mut self, target: Expression<'src>) -> Result<Expression<'src>, ParseError> {
+        let equals = self.previous().span;
+        // assignment is right-associative, so the value may itself be an assignment
+        let value = self.parse_precedence(Precedence::Assignment)?;
+
+        match target {
+            Expression::Variable { name, depth } => {
+                let span = name.span.to(value.span());
+                Ok(Expression::Assign {
+                    name,
+                    value: Box::new(value),
+                    span,
+                    depth,
+                })
+            }
+            Expression::Get { object, name, .. } => {
+                let span = object.span().to(value.span());
+                Ok(Expression::Set {
+                    object,
+                    name,
+                    value: Box::new(value),
+                    span,
+                })
+            }
+            _ => {
+                // the parser isn't confused about where it is, so report without unwinding
+                self.errors.push(
+                    ParseError::new(
+                        ParseErrorKind::InvalidAssignmentTarget,
+                        equals,
+                        String::from("Invalid assignment target."),
+                    )
+                    .with_help("only variables and fields can be assigned to"),
+                );
+                Ok(target)
+            }
+        }
+    }
+
+    fn get(&mut self, object: Expression<'src>) -> Result<Expression<'src>, ParseError> {
+        let name = self.consume(Expected::Identifier, "Expect property name after '.'.")?;
+        let span = object.span().to(name.span);
+        Ok(Expression::Get {
+            object: Box::new(object),
+            name,
+            span,
+        })
+    }
+
+    fn call(&mut self, callee: Expression<'src>) -> Result<Expression<'src>, ParseError> {
+        let left_paren = self.previous().span;
+        let mut arguments: Vec<Expression<'src>> = Vec::new();
+        if !self.check(TokenType::RightParen) {
+            loop {
+                if arguments.len() >= MAX_ARGUMENTS {
+                    self.errors.push(ParseError::new(
+                        ParseErrorKind::TooManyArguments,
+                        self.peek().span,
+                        format!("Can't have more than {} arguments.", MAX_ARGUMENTS),
+                    ));
+                }
+                arguments.push(self.expression()?);
+                if !self.is_matching(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+
+        let paren = self.consume_closing(
+            Expected::RightParen,
+            "Expect ')' after arguments.",
+            left_paren,
+            "opening parenthesis here",
+        )?;
+        let span = callee.span().to(paren.span);
+        Ok(Expression::Call {
+            callee: Box::new(callee),
+            paren,
+            arguments,
+            span,
+        })
+    }
+
+    fn consume(&mut self, expected: Expected, msg: &str) -> Result<Token<'src>, ParseError> {
+        if self.check(expected.token_type()) {
+            return Ok(self.advance().clone());
+        }
+        Err(ParseError::new(
+            ParseErrorKind::ExpectedToken(expected),
+            self.peek().span,
+            String::from(msg),
+        ))
+    }
+
+    // like `consume`, but an error also points at the token opening what is being closed
+    fn consume_closing(
+        &mut self,
+        expected: Expected,
+        msg: &str,
+        opening: Span,
+        label: &str,
+    ) -> Result<Token<'src>, ParseError> {
+        self.consume(expected, msg)
+            .map_err(|err| err.with_label(opening, label))
+    }
+
+    // discards tokens until it's likely at the beginning of the next statement
+    fn synchronize(&mut self) {
+        self.advance();
+        while !self.is_at_end() {
+            if self.previous().token_type == TokenType::Semicolon {
+                return;
+            }
+            match self.peek().token_type {
+                TokenType::Class
+                | TokenType::Fun
+                | TokenType::Var
+                | TokenType::For
+                | TokenType::If
+                | TokenType::While
+                | TokenType::Print
+                | TokenType::Return => return,
+                _ => {
+                    self.advance();
+                }
+            }
+        }
+    }
+
+    fn is_matching(&mut self, t_types: &[TokenType]) -> bool {
+        for &tt in t_types {
+            if self.check(tt) {
+                self.advance();
+                return true;
+            }
+        }
+        false
+    }
+
+    fn check(&self, tt: TokenType) -> bool {
+        if self.is_at_end() {
+            return false;
+        }
+        self.peek().token_type == tt
+    }
+
+    fn advance(&mut self) -> &Token<'src> {
+        if !(self.is_at_end()) {
+            let next = self.next_token();
+            self.previous = std::mem::replace(&mut self.current, next);
+        }
+        self.previous()
+    }
+
+    // pulls the next token that matters to the grammar
+    fn next_token(&mut self) -> Token<'src> {
+        for token in self.tokens.by_ref() {
+            // comments carry no meaning for the grammar
+            if token.token_type != TokenType::Comment {
+                return token;
+            }
+        }
+        // the parser relies on the token stream being terminated by an EOF token
+        let end = self.current.span;
+        Token::new(
+            TokenType::Eof,
+            "",
+            None,
+            Span::new(end.end, end.end, end.line, end.column),
+        )
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.peek().token_type == TokenType::Eof
+    }
+
+    fn peek(&self) -> &Token<'src> {
+        &self.current
+    }
+
+    fn previous(&self) -> &Token<'src> {
+        &self.previous
+    }
+}
+
+// how tightly an operator binds its operands, from loosest to tightest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Precedence {
+    None,
+    Assignment,
+    Or,
+    And,
+    Equality,
+    Comparison,
+    Term,
+    Factor,
+    Unary,
+    Call,
+}
+
+impl Precedence {
+    // the next tighter level
+    fn next(self) -> Precedence {
+        match self {
+            Precedence::None => Precedence::Assignment,
+            Precedence::Assignment => Precedence::Or,
+            Precedence::Or => Precedence::And,
+            Precedence::And => Precedence::Equality,
+            Precedence::Equality => Precedence::Comparison,
+            Precedence::Comparison => Precedence::Term,
+            Precedence::Term => Precedence::Factor,
+            Precedence::Factor => Precedence::Unary,
+            Precedence::Unary | Precedence::Call => Precedence::Call,
+        }
+    }
+}
+
+// parses an expression starting with the token just consumed
+type PrefixRule<'src, I> = fn(&mut Parser<'src, I>) -> Result<Expression<'src>, ParseError>;
+// parses the rest of an expression whose left operand has already been parsed, the
+// operator being the token just consumed
+type InfixRule<'src, I> =
+    fn(&mut Parser<'src, I>, Expression<'src>) -> Result<Expression<'src>, ParseError>;
+
+struct ParseRule<'src, I> {
+    prefix: Option<PrefixRule<'src, I>>,
+    infix: Option<InfixRule<'src, I>>,
+    // the precedence of the token as an infix operator
+    precedence: Precedence,
+}
+
+// the expression grammar: which rules apply to each token, and how tightly it binds
+fn rule<'src, I: Iterator<Item = Token<'src>>>(token_type: TokenType) -> ParseRule<'src, I> {
+    let (prefix, infix, precedence): (Option<PrefixRule<I>>, Option<InfixRule<I>>, Precedence) =
+        match token_type {
+            TokenType::LeftParen => (Some(Parser::grouping), Some(Parser::call), Precedence::Call),
+            TokenType::Dot => (None, Some(Parser::get), Precedence::Call),
+            TokenType::Minus => (Some(Parser::unary), Some(Parser::binary), Precedence::Term),
+            TokenType::Plus => (None, Some(Parser::binary), Precedence::Term),
+            TokenType::Slash | TokenType::Star => (None, Some(Parser::binary), Precedence::Factor),
+            TokenType::Bang => (Some(Parser::unary), None, Precedence::None),
+            TokenType::BangEqual | TokenType::EqualEqual => {
+                (None, Some(Parser::binary), Precedence::Equality)
+            }
+            TokenType::Greater
+            | TokenType::GreaterEqual
+            | TokenType::Less
+            | TokenType::LessEqual => (None, Some(Parser::binary), Precedence::Comparison),
+            TokenType::Equal => (None, Some(Parser::assignment), Precedence::Assignment),
+            TokenType::And => (None, Some(Parser::logical), Precedence::And),
+            TokenType::Or => (None, Some(Parser::logical), Precedence::Or),
+            TokenType::Identifier => (Some(Parser::variable), None, Precedence::None),
+            TokenType::String
+            | TokenType::Number
+            | TokenType::False
+            | TokenType::True
+            | TokenType::Nil => (Some(Parser::literal), None, Precedence::None),
+            TokenType::This => (Some(Parser::this), None, Precedence::None),
+            TokenType::Super => (Some(Parser::super_method), None, Precedence::None),
+            _ => (None, None, Precedence::None),
+        };
+    ParseRule {
+        prefix,
+        infix,
+        precedence,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::lexer::scan_tokens;
+    use crate::scanner::lexer::Lexer;
+    use crate::scanner::token::Literal;
+
+    #[test]
+    fn test_build_tree() {
+        // 1 + 2
+
+        let one = Token::new(
+            TokenType::Number,
+            "1",
+            Some(Literal::Numeric(1.0)),
+            Span::new(0, 1, 1, 1),
+        );
+        let plus = Token::new(TokenType::Plus, "+", None, Span::new(2, 3, 1, 3));
+        let two = Token::new(
+            TokenType::Number,
+            "2",
+            Some(Literal::Numeric(2.0)),
+            Span::new(4, 5, 1, 5),
+        );
+        let eof = Token::new(TokenType::Eof, "", None, Span::new(5, 5, 1, 6));
+
+        let tokens: Vec<Token> = vec![one.clone(), plus.clone(), two.clone(), eof];
+
+        let expected_tree = Expression::Binary {
+            left: Box::new(Expression::Literal(one)),
+            operator: plus,
+            right: Box::new(Expression::Literal(two)),
+            span: Span::new(0, 5, 1, 1),
+        };
+
+        let mut parser = Parser::new(tokens);
+        let tree = parser.expression().unwrap();
+
+        assert_eq!(tree, expected_tree);
+    }
+
+    #[test]
+    fn test_missing_right_paren() {
+        let tokens = scan_tokens("(1 + 2").unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let err = parser.expression().unwrap_err();
+
+        assert_eq!(
+            err,
+            ParseError::new(
+                ParseErrorKind::ExpectedToken(Expected::RightParen),
+                Span::new(6, 6, 1, 7),
+                String::from("Expect ')' after expression."),
+            )
+            .with_label(Span::new(0, 1, 1, 1), "opening parenthesis here")
+        );
+    }
+
+    #[test]
+    fn test_expected_expression() {
+        let mut parser = Parser::new(vec![]);
+        let err = parser.expression().unwrap_err();
+
+        assert_eq!(err.kind, ParseErrorKind::ExpectedExpression);
+    }
+
+    #[test]
+    fn test_parse_recovers_after_errors() {
+        let tokens = scan_tokens("1 + ; (2 * 3; 4 - 1; )").unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let (statements, errors) = parser.parse();
+
+        assert_eq!(statements.len(), 1);
+        match &statements[0] {
+            Stmt::Expression(expr) => assert_eq!(expr.span(), Span::new(14, 19, 1, 15)),
+            stmt => panic!("expected expression statement, got {:?}", stmt),
+        }
+
+        let expected_kinds = [
+            ParseErrorKind::ExpectedExpression,
+            ParseErrorKind::ExpectedToken(Expected::RightParen),
+            ParseErrorKind::ExpectedExpression,
+        ];
+        assert_eq!(errors.len(), expected_kinds.len());
+        for (i, _) in errors.iter().enumerate() {
+            assert_eq!(errors[i].kind, expected_kinds[i]);
+        }
+    }
+
+    #[test]
+    fn test_parse_statements() {
+        let tokens = scan_tokens("var a = 1; var b; print a; b;").unwrap();
+
+        let mut parser = Parser::new(tokens.clone());
+        let (statements, errors) = parser.parse();
+
+        assert!(errors.is_empty());
+
+        let expected_statements = vec![
+            Stmt::Var {
+                name: tokens[1].clone(),
+                initializer: Some(Expression::Literal(tokens[3].clone())),
+            },
+            Stmt::Var {
+                name: tokens[6].clone(),
+                initializer: None,
+            },
+            Stmt::Print(Expression::variable(tokens[9].clone())),
+            Stmt::Expression(Expression::variable(tokens[11].clone())),
+        ];
+
+        assert_eq!(statements, expected_statements);
+    }
+
+    #[test]
+    fn test_parse_skips_line_comments() {
+        let tokens = scan_tokens("// one\nprint 1; // two").unwrap();
+
+        let mut parser = Parser::new(tokens.clone());
+        let (statements, errors) = parser.parse();
+
+        assert!(errors.is_empty());
+        assert_eq!(
+            statements,
+            vec![Stmt::Print(Expression::Literal(tokens[2].clone()))]
+        );
+    }
+
+    #[test]
+    fn test_parse_skips_block_comments() {
+        let tokens = scan_tokens("print /* one /* two */ */ 1;").unwrap();
+
+        let (statements, errors) = Parser::new(tokens.clone()).parse();
+
+        assert!(errors.is_empty());
+        assert_eq!(
+            statements,
+            vec![Stmt::Print(Expression::Literal(tokens[2].clone()))]
+        );
+    }
+
+    #[test]
+    fn test_parse_pulls_tokens_from_lexer() {
+        let source = "var a = 1; // one\nprint a + 2;";
+
+        let lexer = Lexer::new(source).map(|token| token.unwrap());
+        let (statements, errors) = Parser::new(lexer).parse();
+
+        assert!(errors.is_empty());
+        assert_eq!(
+            statements,
+            Parser::new(scan_tokens(source).unwrap()).parse().0
+        );
+    }
+
+    #[test]
+    fn test_parse_var_requires_name() {
+        let tokens = scan_tokens("var 1 = 2; print 3;").unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let (statements, errors) = parser.parse();
+
+        assert_eq!(
+            statements,
+            vec![Stmt::Print(Expression::Literal(Token::new(
+                TokenType::Number,
+                "3",
+                Some(Literal::Numeric(3.0)),
+                Span::new(17, 18, 1, 18),
+            )))]
+        );
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].kind,
+            ParseErrorKind::ExpectedToken(Expected::Identifier)
+        );
+        assert_eq!(errors[0].message, "Expect variable name.");
+    }
+
+    #[test]
+    fn test_parse_expression_requires_end_of_input() {
+        let tokens = scan_tokens("1 + 2").unwrap();
+        assert!(Parser::new(tokens).parse_expression().is_ok());
+
+        let tokens = scan_tokens("1 + 2;").unwrap();
+        let err = Parser::new(tokens).parse_expression().unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::ExpectedToken(Expected::Eof));
+    }
+
+    #[test]
+    fn test_parse_expression_reports_recovered_errors() {
+        let tokens = scan_tokens("1 + 2 = 3").unwrap();
+
+        let err = Parser::new(tokens).parse_expression().unwrap_err();
+
+        assert_eq!(err.kind, ParseErrorKind::InvalidAssignmentTarget);
+    }
+
+    #[test]
+    fn test_parse_assignment_is_right_associative() {
+        let tokens = scan_tokens("a = b = 1;").unwrap();
+
+        let (statements, errors) = Parser::new(tokens.clone()).parse();
+
+        assert!(errors.is_empty());
+        assert_eq!(
+            statements,
+            vec![Stmt::Expression(Expression::Assign {
+                name: tokens[0].clone(),
+                value: Box::new(Expression::Assign {
+                    name: tokens[2].clone(),
+                    value: Box::new(Expression::Literal(tokens[4].clone())),
+                    span: Span::new(4, 9, 1, 5),
+                    depth: Cell::new(None),
+                }),
+                span: Span::new(0, 9, 1, 1),
+                depth: Cell::new(None),
+            })]
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_assignment_target() {
+        let tokens = scan_tokens("a + b = 1; print 2;").unwrap();
+
+        let (statements, errors) = Parser::new(tokens).parse();
+
+        assert_eq!(statements.len(), 2);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind, ParseErrorKind::InvalidAssignmentTarget);
+        assert_eq!(errors[0].span, Span::new(6, 7, 1, 7));
+    }
+
+    #[test]
+    fn test_parse_block() {
+        let tokens = scan_tokens("{ var a; { a; } }").unwrap();
+
+        let (statements, errors) = Parser::new(tokens.clone()).parse();
+
+        assert!(errors.is_empty());
+        assert_eq!(
+            statements,
+            vec![Stmt::Block(vec![
+                Stmt::Var {
+                    name: tokens[2].clone(),
+                    initializer: None,
+                },
+                Stmt::Block(vec![Stmt::Expression(Expression::variable(
+                    tokens[5].clone()
+                ))]),
+            ])]
+        );
+    }
+
+    #[test]
+    fn test_parse_unterminated_block() {
+        let tokens = scan_tokens("{ print 1;").unwrap();
+
+        let (_, errors) = Parser::new(tokens).parse();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].kind,
+            ParseErrorKind::ExpectedToken(Expected::RightBrace)
+        );
+    }
+
+    #[test]
+    fn test_parse_if_else_binds_to_nearest_if() {
+        let tokens = scan_tokens("if (a) if (b) 1; else 2;").unwrap();
+
+        let (statements, errors) = Parser::new(tokens).parse();
+
+        assert!(errors.is_empty());
+        match &statements[0] {
+            Stmt::If {
+                then_branch,
+                else_branch: None,
+                ..
+            } => assert!(matches!(
+                **then_branch,
+                Stmt::If {
+                    else_branch: Some(_),
+                    ..
+                }
+            )),
+            stmt => panic!("expected if statement without else, got {:?}", stmt),
+        }
+    }
+
+    #[test]
+    fn test_parse_logical_precedence() {
+        // `and` binds tighter than `or`
+        let tokens = scan_tokens("a or b and c;").unwrap();
+
+        let (statements, errors) = Parser::new(tokens.clone()).parse();
+
+        assert!(errors.is_empty());
+        assert_eq!(
+            statements,
+            vec![Stmt::Expression(Expression::Logical {
+                left: Box::new(Expression::variable(tokens[0].clone())),
+                operator: tokens[1].clone(),
+                right: Box::new(Expression::Logical {
+                    left: Box::new(Expression::variable(tokens[2].clone())),
+                    operator: tokens[3].clone(),
+                    right: Box::new(Expression::variable(tokens[4].clone())),
+                    span: Span::new(5, 12, 1, 6),
+                }),
+                span: Span::new(0, 12, 1, 1),
+            })]
+        );
+    }
+
+    #[test]
+    fn test_parse_operator_precedence_and_associativity() {
+        // parses as `((1 - 2) - (3 * (-4))) < 5) == true`
+        let tokens = scan_tokens("1 - 2 - 3 * -4 < 5 == true").unwrap();
+
+        let expr = Parser::new(tokens).parse_expression().unwrap();
+
+        let Expression::Binary { left, operator, .. } = expr else {
+            panic!("expected an equality, got {:?}", expr);
+        };
+        assert_eq!(operator.token_type, TokenType::EqualEqual);
+        let Expression::Binary { left, operator, .. } = *left else {
+            panic!("expected a comparison");
+        };
+        assert_eq!(operator.token_type, TokenType::Less);
+        let Expression::Binary {
+            left,
+            operator,
+            right,
+            ..
+        } = *left
+        else {
+            panic!("expected a subtraction");
+        };
+        assert_eq!(operator.token_type, TokenType::Minus);
+        assert!(matches!(
+            *left,
+            Expression::Binary { ref operator, .. } if operator.token_type == TokenType::Minus
+        ));
+        let Expression::Binary {
+            operator, right, ..
+        } = *right
+        else {
+            panic!("expected a multiplication");
+        };
+        assert_eq!(operator.token_type, TokenType::Star);
+        assert!(matches!(*right, Expression::Unary { .. }));
+    }
+
+    #[test]
+    fn test_parse_for_desugars_to_while() {
+        let tokens = scan_tokens("for (var i = 0; i < 3; i = i + 1) print i;").unwrap();
+
+        let (statements, errors) = Parser::new(tokens).parse();
+
+        assert!(errors.is_empty());
+        match &statements[..] {
+            [Stmt::Block(outer)] => match &outer[..] {
+                [Stmt::Var { .. }, Stmt::While { body, .. }] => match &**body {
+                    Stmt::Block(inner) => assert!(matches!(
+                        &inner[..],
+                        [Stmt::Print(_), Stmt::Expression(Expression::Assign { .. })]
+                    )),
+                    stmt => panic!("expected loop body block, got {:?}", stmt),
+                },
+                stmts => panic!("expected initializer and while, got {:?}", stmts),
+            },
+            stmts => panic!("expected a single block, got {:?}", stmts),
+        }
+    }
+
+    #[test]
+    fn test_parse_for_without_clauses() {
+        let tokens = scan_tokens("for (;;) print 1;").unwrap();
+
+        let (statements, errors) = Parser::new(tokens).parse();
+
+        assert!(errors.is_empty());
+        match &statements[..] {
+            [Stmt::While {
+                condition: Expression::Literal(token),
+                ..
+            }] => assert_eq!(token.token_type, TokenType::True),
+            stmts => panic!("expected a bare while loop, got {:?}", stmts),
+        }
+    }
+
+    #[test]
+    fn test_parse_function_declaration() {
+        let tokens = scan_tokens("fun add(a, b) { return a + b; }").unwrap();
+
+        let (statements, errors) = Parser::new(tokens.clone()).parse();
+
+        assert!(errors.is_empty());
+        match &statements[..] {
+            [Stmt::Function(decl)] => {
+                assert_eq!(decl.name, tokens[1]);
+                assert_eq!(decl.params, vec![tokens[3].clone(), tokens[5].clone()]);
+                assert!(matches!(
+                    &decl.body[..],
+                    [Stmt::Return {
+                        value: Some(Expression::Binary { .. }),
+                        ..
+                    }]
+                ));
+            }
+            stmts => panic!("expected a function declaration, got {:?}", stmts),
+        }
+    }
+
+    #[test]
+    fn test_parse_chained_calls() {
+        let tokens = scan_tokens("f(1)(2, 3);").unwrap();
+
+        let (statements, errors) = Parser::new(tokens).parse();
+
+        assert!(errors.is_empty());
+        match &statements[..] {
+            [Stmt::Expression(Expression::Call {
+                callee, arguments, ..
+            })] => {
+                assert_eq!(arguments.len(), 2);
+                assert!(matches!(**callee, Expression::Call { .. }));
+            }
+            stmts => panic!("expected a call, got {:?}", stmts),
+        }
+    }
+
+    #[test]
+    fn test_parse_too_many_arguments() {
+        let arguments = vec!["1"; MAX_ARGUMENTS + 1].join(", ");
+        let source = format!("f({});", arguments);
+        let tokens = scan_tokens(&source).unwrap();
+
+        let (statements, errors) = Parser::new(tokens).parse();
+
+        // the error is reported but the call is still parsed
+        assert_eq!(statements.len(), 1);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind, ParseErrorKind::TooManyArguments);
+        assert_eq!(errors[0].message, "Can't have more than 255 arguments.");
+    }
+
+    #[test]
+    fn test_parse_too_deeply_nested() {
+        for source in [
+            format!("print {}1;", "-".repeat(20_000)),
+            format!("print {}1{};", "(".repeat(200_000), ")".repeat(200_000)),
+        ] {
+            let tokens = scan_tokens(&source).unwrap();
+
+            let (statements, errors) = Parser::new(tokens).parse();
+
+            assert!(statements.is_empty());
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].kind, ParseErrorKind::TooDeeplyNested);
+            assert_eq!(
+                errors[0].message,
+                "Can't nest expressions more than 256 deep."
+            );
+        }
+
+        // nesting up to the limit is fine
+        let source = format!("print {}1;", "-".repeat(MAX_NESTING_DEPTH - 1));
+        let (_, errors) = Parser::new(scan_tokens(&source).unwrap()).parse();
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn test_parse_class_declaration() {
+        let tokens =
+            scan_tokens("class B < A { init(x) { this.x = x; } get() { return super.get(); } }")
+                .unwrap();
+
+        let (statements, errors) = Parser::new(tokens.clone()).parse();
+
+        assert!(errors.is_empty());
+        match &statements[..] {
+            [Stmt::Class {
+                name,
+                superclass:
+                    Some(Expression::Variable {
+                        name: superclass, ..
+                    }),
+                methods,
+            }] => {
+                assert_eq!(name, &tokens[1]);
+                assert_eq!(superclass, &tokens[3]);
+                assert_eq!(methods.len(), 2);
+                assert!(matches!(
+                    &methods[0].body[..],
+                    [Stmt::Expression(Expression::Set { .. })]
+                ));
+                assert!(matches!(
+                    &methods[1].body[..],
+                    [Stmt::Return {
+                        value: Some(Expression::Call { .. }),
+                        ..
+                    }]
+                ));
+            }
+            stmts => panic!("expected a class declaration, got {:?}", stmts),
+        }
+    }
+
+    #[test]
+    fn test_parse_property_chain() {
+        let tokens = scan_tokens("a.b.c = d.e();").unwrap();
+
+        let (statements, errors) = Parser::new(tokens.clone()).parse();
+
+        assert!(errors.is_empty());
+        match &statements[..] {
+            [Stmt::Expression(Expression::Set {
+                object,
+                name,
+                value,
+                ..
+            })] => {
+                assert_eq!(name, &tokens[4]);
+                assert!(matches!(**object, Expression::Get { .. }));
+                assert!(matches!(**value, Expression::Call { .. }));
+            }
+            stmts => panic!("expected a property assignment, got {:?}", stmts),
+        }
+    }
+
+    #[test]
+    fn test_grouping_span() {
+        // -(1 + 2)
+        let tokens = scan_tokens("-(1 + 2)").unwrap();
+
+        let mut parser = Parser::new(tokens);
+        let tree = parser.expression().unwrap();
+
+        assert_eq!(tree.span(), Span::new(0, 8, 1, 1));
+        match tree {
+            Expression::Unary { right, .. } => assert_eq!(right.span(), Span::new(1, 8, 1, 2)),
+            _ => panic!("expected unary expression, got {:?}", tree),
+        }
+    }
+}
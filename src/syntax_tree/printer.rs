@@ -0,0 +1,403 @@
+use crate::scanner::token::Literal;
+use crate::scanner::token::Token;
+use crate::scanner::token_type::TokenType;
+use crate::syntax_tree::expression::Expression;
+use crate::syntax_tree::statement::FunctionDecl;
+use crate::syntax_tree::statement::Stmt;
+use crate::syntax_tree::visitor::Visitor;
+
+// prints the syntax tree fully parenthesized in prefix form, as in
+// `(* (- 123) (group 45.67))`, which makes the parsed precedence explicit
+pub struct AstPrinter;
+
+impl AstPrinter {
+    pub fn print(statements: &[Stmt]) -> String {
+        statements
+            .iter()
+            .map(|stmt| AstPrinter.visit_statement(stmt))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    fn parenthesize<'src>(&mut self, name: &str, exprs: &[&Expression<'src>]) -> String {
+        let mut out = format!("({}", name);
+        for expr in exprs {
+            out.push(' ');
+            out.push_str(&self.visit_expression(expr));
+        }
+        out.push(')');
+        out
+    }
+}
+
+impl<'src> Visitor<'src> for AstPrinter {
+    type Output = String;
+
+    fn visit_expression(&mut self, expr: &Expression<'src>) -> String {
+        match expr {
+            Expression::Unary {
+                operator, right, ..
+            } => self.parenthesize(operator.lexeme, &[right]),
+            Expression::Binary {
+                left,
+                operator,
+                right,
+                ..
+            }
+            | Expression::Logical {
+                left,
+                operator,
+                right,
+                ..
+            } => self.parenthesize(operator.lexeme, &[left, right]),
+            Expression::Literal(token) => literal(token),
+            Expression::Grouping { expression, .. } => self.parenthesize("group", &[expression]),
+            Expression::Call {
+                callee, arguments, ..
+            } => {
+                let mut exprs: Vec<&Expression> = vec![callee];
+                exprs.extend(arguments.iter());
+                self.parenthesize("call", &exprs)
+            }
+            Expression::Get { object, name, .. } => {
+                format!("(. {} {})", self.visit_expression(object), name.lexeme)
+            }
+            Expression::Set {
+                object,
+                name,
+                value,
+                ..
+            } => format!(
+                "(= (. {} {}) {})",
+                self.visit_expression(object),
+                name.lexeme,
+                self.visit_expression(value)
+            ),
+            Expression::Super { method, .. } => format!("(super {})", method.lexeme),
+            Expression::This { .. } => String::from("this"),
+            Expression::Variable { name, .. } => name.lexeme.to_string(),
+            Expression::Assign { name, value, .. } => {
+                format!("(= {} {})", name.lexeme, self.visit_expression(value))
+            }
+        }
+    }
+
+    fn visit_statement(&mut self, stmt: &Stmt<'src>) -> String {
+        match stmt {
+            Stmt::Expression(expr) => self.parenthesize(";", &[expr]),
+            Stmt::Print(expr) => self.parenthesize("print", &[expr]),
+            Stmt::Function(declaration) => self.visit_function(declaration),
+            Stmt::Return { value: None, .. } => String::from("(return)"),
+            Stmt::Return {
+                value: Some(value), ..
+            } => self.parenthesize("return", &[value]),
+            Stmt::Var {
+                name,
+                initializer: None,
+            } => format!("(var {})", name.lexeme),
+            Stmt::Var {
+                name,
+                initializer: Some(initializer),
+            } => format!(
+                "(var {} = {})",
+                name.lexeme,
+                self.visit_expression(initializer)
+            ),
+            Stmt::Block(statements) => {
+                let mut out = String::from("(block");
+                for stmt in statements {
+                    out.push(' ');
+                    out.push_str(&self.visit_statement(stmt));
+                }
+                out.push(')');
+                out
+            }
+            Stmt::Class {
+                name,
+                superclass,
+                methods,
+            } => {
+                let mut out = format!("(class {}", name.lexeme);
+                if let Some(superclass) = superclass {
+                    out.push_str(" < ");
+                    out.push_str(&self.visit_expression(superclass));
+                }
+                for method in methods {
+                    out.push(' ');
+                    out.push_str(&self.visit_function(method));
+                }
+                out.push(')');
+                out
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch: None,
+            } => format!(
+                "(if {} {})",
+                self.visit_expression(condition),
+                self.visit_statement(then_branch)
+            ),
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch: Some(else_branch),
+            } => format!(
+                "(if-else {} {} {})",
+                self.visit_expression(condition),
+                self.visit_statement(then_branch),
+                self.visit_statement(else_branch)
+            ),
+            Stmt::While { condition, body } => format!(
+                "(while {} {})",
+                self.visit_expression(condition),
+                self.visit_statement(body)
+            ),
+        }
+    }
+
+    fn visit_function(&mut self, declaration: &FunctionDecl<'src>) -> String {
+        let params: Vec<&str> = declaration.params.iter().map(|p| p.lexeme).collect();
+        let mut out = format!("(fun {}({})", declaration.name.lexeme, params.join(" "));
+        for stmt in declaration.body.iter() {
+            out.push(' ');
+            out.push_str(&self.visit_statement(stmt));
+        }
+        out.push(')');
+        out
+    }
+}
+
+// prints the syntax tree in reverse Polish notation, as in `1 2 + 4 3 - *`, where
+// every operator follows its operands. Unary minus is written `~` to tell it apart
+// from subtraction, and calls are written `call/N` with N the number of arguments.
+pub struct RpnPrinter;
+
+impl RpnPrinter {
+    pub fn print(statements: &[Stmt]) -> String {
+        statements
+            .iter()
+            .map(|stmt| RpnPrinter.visit_statement(stmt))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    fn postfix<'src>(&mut self, exprs: &[&Expression<'src>], operator: &str) -> String {
+        let mut parts: Vec<String> = exprs.iter().map(|e| self.visit_expression(e)).collect();
+        parts.push(operator.to_string());
+        parts.join(" ")
+    }
+}
+
+impl<'src> Visitor<'src> for RpnPrinter {
+    type Output = String;
+
+    fn visit_expression(&mut self, expr: &Expression<'src>) -> String {
+        match expr {
+            Expression::Unary {
+                operator, right, ..
+            } => match operator.lexeme {
+                "-" => self.postfix(&[right], "~"),
+                operator => self.postfix(&[right], operator),
+            },
+            Expression::Binary {
+                left,
+                operator,
+                right,
+                ..
+            }
+            | Expression::Logical {
+                left,
+                operator,
+                right,
+                ..
+            } => self.postfix(&[left, right], operator.lexeme),
+            Expression::Literal(token) => literal(token),
+            // the order of operations is already explicit without parentheses
+            Expression::Grouping { expression, .. } => self.visit_expression(expression),
+            Expression::Call {
+                callee, arguments, ..
+            } => {
+                let mut exprs: Vec<&Expression> = vec![callee];
+                exprs.extend(arguments.iter());
+                self.postfix(&exprs, &format!("call/{}", arguments.len()))
+            }
+            Expression::Get { object, name, .. } => {
+                self.postfix(&[object], &format!(".{}", name.lexeme))
+            }
+            Expression::Set {
+                object,
+                name,
+                value,
+                ..
+            } => self.postfix(&[object, value], &format!(".{}=", name.lexeme)),
+            Expression::Super { method, .. } => format!("super.{}", method.lexeme),
+            Expression::This { .. } => String::from("this"),
+            Expression::Variable { name, .. } => name.lexeme.to_string(),
+            Expression::Assign { name, value, .. } => {
+                self.postfix(&[value], &format!("{}=", name.lexeme))
+            }
+        }
+    }
+
+    // statements are written like operators after the expressions they consume
+    fn visit_statement(&mut self, stmt: &Stmt<'src>) -> String {
+        match stmt {
+            Stmt::Expression(expr) => self.postfix(&[expr], ";"),
+            Stmt::Print(expr) => self.postfix(&[expr], "print"),
+            Stmt::Function(declaration) => self.visit_function(declaration),
+            Stmt::Return { value, .. } => match value {
+                Some(value) => self.postfix(&[value], "return"),
+                None => String::from("return"),
+            },
+            Stmt::Var { name, initializer } => match initializer {
+                Some(initializer) => self.postfix(&[initializer], &format!("var {}", name.lexeme)),
+                None => format!("var {}", name.lexeme),
+            },
+            Stmt::Block(statements) => {
+                let mut parts = vec![String::from("{")];
+                parts.extend(statements.iter().map(|stmt| self.visit_statement(stmt)));
+                parts.push(String::from("}"));
+                parts.join(" ")
+            }
+            Stmt::Class {
+                name,
+                superclass,
+                methods,
+            } => {
+                let mut parts: Vec<String> = superclass
+                    .iter()
+                    .map(|superclass| self.visit_expression(superclass))
+                    .collect();
+                parts.push(String::from("{"));
+                parts.extend(methods.iter().map(|method| self.visit_function(method)));
+                parts.push(String::from("}"));
+                match superclass {
+                    Some(_) => parts.push(format!("class {} <", name.lexeme)),
+                    None => parts.push(format!("class {}", name.lexeme)),
+                }
+                parts.join(" ")
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let mut parts = vec![
+                    self.visit_expression(condition),
+                    self.visit_statement(then_branch),
+                ];
+                match else_branch {
+                    Some(else_branch) => {
+                        parts.push(self.visit_statement(else_branch));
+                        parts.push(String::from("if-else"));
+                    }
+                    None => parts.push(String::from("if")),
+                }
+                parts.join(" ")
+            }
+            Stmt::While { condition, body } => format!(
+                "{} {} while",
+                self.visit_expression(condition),
+                self.visit_statement(body)
+            ),
+        }
+    }
+
+    fn visit_function(&mut self, declaration: &FunctionDecl<'src>) -> String {
+        let mut parts = vec![String::from("{")];
+        parts.extend(
+            declaration
+                .body
+                .iter()
+                .map(|stmt| self.visit_statement(stmt)),
+        );
+        parts.push(String::from("}"));
+        let params: Vec<&str> = declaration.params.iter().map(|p| p.lexeme).collect();
+        parts.push(format!(
+            "fun {}({})",
+            declaration.name.lexeme,
+            params.join(" ")
+        ));
+        parts.join(" ")
+    }
+}
+
+// numbers are printed by value so that `1.0` and `1` read the same, strings as
+// written. Keywords are spelled out since the parser may synthesize them.
+fn literal(token: &Token) -> String {
+    match (token.token_type, &token.literal) {
+        (_, Some(Literal::Numeric(n))) => n.to_string(),
+        (TokenType::True, _) => String::from("true"),
+        (TokenType::False, _) => String::from("false"),
+        (TokenType::Nil, _) => String::from("nil"),
+        _ => token.lexeme.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::lexer::scan_tokens;
+    use crate::syntax_tree::parser::Parser;
+
+    fn lisp(source: &str) -> String {
+        let (statements, errors) = Parser::new(scan_tokens(source).unwrap()).parse();
+        assert!(errors.is_empty(), "unexpected parse errors: {:?}", errors);
+        AstPrinter::print(&statements)
+    }
+
+    fn rpn(source: &str) -> String {
+        let (statements, errors) = Parser::new(scan_tokens(source).unwrap()).parse();
+        assert!(errors.is_empty(), "unexpected parse errors: {:?}", errors);
+        RpnPrinter::print(&statements)
+    }
+
+    #[test]
+    fn test_ast_printer_expressions() {
+        assert_eq!(lisp("-123 * (45.67);"), "(; (* (- 123) (group 45.67)))");
+        assert_eq!(
+            lisp("a = b.c = !d or e and f(1, \"s\") >= 2;"),
+            "(; (= a (= (. b c) (or (! d) (and e (>= (call f 1 \"s\") 2))))))"
+        );
+        assert_eq!(
+            lisp("print super.m(this);"),
+            "(print (call (super m) this))"
+        );
+    }
+
+    #[test]
+    fn test_ast_printer_statements() {
+        assert_eq!(
+            lisp(
+                "var a; var b = 1.0; { a; } if (a) print 1; else return; \
+                 while (a) a = nil; for (;;) a;"
+            ),
+            "(var a)\n(var b = 1)\n(block (; a))\n(if-else a (print 1) (return))\n\
+             (while a (; (= a nil)))\n(while true (; a))"
+        );
+        assert_eq!(
+            lisp("class B < A { m(x, y) { return x; } } fun f() {}"),
+            "(class B < A (fun m(x y) (return x)))\n(fun f())"
+        );
+    }
+
+    #[test]
+    fn test_rpn_printer_expressions() {
+        assert_eq!(rpn("(1 + 2) * (4 - 3);"), "1 2 + 4 3 - * ;");
+        assert_eq!(rpn("-a - !b;"), "a ~ b ! - ;");
+        assert_eq!(rpn("x = o.p = f(1, 2).q;"), "o f 1 2 call/2 .q .p= x= ;");
+    }
+
+    #[test]
+    fn test_rpn_printer_statements() {
+        assert_eq!(
+            rpn("var a = 1; if (a) print a; else { a; } while (a) return;"),
+            "1 var a\na a print { a ; } if-else\na return while"
+        );
+        assert_eq!(
+            rpn("class B < A { m(x) { return x; } }"),
+            "A { { x return } fun m(x) } class B <"
+        );
+    }
+}
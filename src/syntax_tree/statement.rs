@@ -0,0 +1,44 @@
+use std::rc::Rc;
+
+use crate::scanner::token::Token;
+use crate::syntax_tree::expression::Expression;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stmt<'src> {
+    Expression(Expression<'src>),
+    // shared so that every closure created from the declaration can refer to it
+    Function(Rc<FunctionDecl<'src>>),
+    Print(Expression<'src>),
+    Return {
+        keyword: Token<'src>,
+        value: Option<Expression<'src>>,
+    },
+    Var {
+        name: Token<'src>,
+        initializer: Option<Expression<'src>>,
+    },
+    Block(Vec<Stmt<'src>>),
+    Class {
+        name: Token<'src>,
+        // always an `Expression::Variable` when present
+        superclass: Option<Expression<'src>>,
+        methods: Vec<Rc<FunctionDecl<'src>>>,
+    },
+    If {
+        condition: Expression<'src>,
+        then_branch: Box<Stmt<'src>>,
+        else_branch: Option<Box<Stmt<'src>>>,
+    },
+    // `for` loops are desugared into `while` loops by the parser
+    While {
+        condition: Expression<'src>,
+        body: Box<Stmt<'src>>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionDecl<'src> {
+    pub name: Token<'src>,
+    pub params: Vec<Token<'src>>,
+    pub body: Vec<Stmt<'src>>,
+}
@@ -0,0 +1,567 @@
+use std::rc::Rc;
+
+use crate::syntax_tree::expression::Expression;
+use crate::syntax_tree::statement::FunctionDecl;
+use crate::syntax_tree::statement::Stmt;
+
+// walks the syntax tree without changing it. Implementors override the methods for
+// the nodes they care about and call the matching `walk_` function to carry on into
+// the children. The walk functions discard what the children return and produce
+// `Output::default()`, so visitors that build a value override every node they
+// need a value from.
+pub trait Visitor<'src>: Sized {
+    type Output: Default;
+
+    fn visit_expression(&mut self, expr: &Expression<'src>) -> Self::Output {
+        walk_expression(self, expr)
+    }
+
+    fn visit_statement(&mut self, stmt: &Stmt<'src>) -> Self::Output {
+        walk_statement(self, stmt)
+    }
+
+    // functions and methods alike
+    fn visit_function(&mut self, declaration: &FunctionDecl<'src>) -> Self::Output {
+        walk_function(self, declaration)
+    }
+}
+
+pub fn walk_expression<'src, V: Visitor<'src>>(
+    visitor: &mut V,
+    expr: &Expression<'src>,
+) -> V::Output {
+    match expr {
+        Expression::Unary { right, .. } => {
+            visitor.visit_expression(right);
+        }
+        Expression::Binary { left, right, .. } | Expression::Logical { left, right, .. } => {
+            visitor.visit_expression(left);
+            visitor.visit_expression(right);
+        }
+        Expression::Grouping { expression, .. } => {
+            visitor.visit_expression(expression);
+        }
+        Expression::Call {
+            callee, arguments, ..
+        } => {
+            visitor.visit_expression(callee);
+            for argument in arguments {
+                visitor.visit_expression(argument);
+            }
+        }
+        Expression::Get { object, .. } => {
+            visitor.visit_expression(object);
+        }
+        Expression::Set { object, value, .. } => {
+            visitor.visit_expression(object);
+            visitor.visit_expression(value);
+        }
+        Expression::Assign { value, .. } => {
+            visitor.visit_expression(value);
+        }
+        Expression::Literal(_)
+        | Expression::Super { .. }
+        | Expression::This { .. }
+        | Expression::Variable { .. } => {}
+    }
+    V::Output::default()
+}
+
+pub fn walk_statement<'src, V: Visitor<'src>>(visitor: &mut V, stmt: &Stmt<'src>) -> V::Output {
+    match stmt {
+        Stmt::Expression(expr) | Stmt::Print(expr) => {
+            visitor.visit_expression(expr);
+        }
+        Stmt::Function(declaration) => {
+            visitor.visit_function(declaration);
+        }
+        Stmt::Return { value, .. } => {
+            if let Some(value) = value {
+                visitor.visit_expression(value);
+            }
+        }
+        Stmt::Var { initializer, .. } => {
+            if let Some(initializer) = initializer {
+                visitor.visit_expression(initializer);
+            }
+        }
+        Stmt::Block(statements) => {
+            for stmt in statements {
+                visitor.visit_statement(stmt);
+            }
+        }
+        Stmt::Class {
+            superclass,
+            methods,
+            ..
+        } => {
+            if let Some(superclass) = superclass {
+                visitor.visit_expression(superclass);
+            }
+            for method in methods {
+                visitor.visit_function(method);
+            }
+        }
+        Stmt::If {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            visitor.visit_expression(condition);
+            visitor.visit_statement(then_branch);
+            if let Some(else_branch) = else_branch {
+                visitor.visit_statement(else_branch);
+            }
+        }
+        Stmt::While { condition, body } => {
+            visitor.visit_expression(condition);
+            visitor.visit_statement(body);
+        }
+    }
+    V::Output::default()
+}
+
+pub fn walk_function<'src, V: Visitor<'src>>(
+    visitor: &mut V,
+    declaration: &FunctionDecl<'src>,
+) -> V::Output {
+    for stmt in declaration.body.iter() {
+        visitor.visit_statement(stmt);
+    }
+    V::Output::default()
+}
+
+// rewrites the syntax tree in place. Like `Visitor`, implementors override the nodes
+// they care about and call the matching `walk_..._mut` function for the rest.
+pub trait MutVisitor<'src>: Sized {
+    fn visit_expression_mut(&mut self, expr: &mut Expression<'src>) {
+        walk_expression_mut(self, expr)
+    }
+
+    fn visit_statement_mut(&mut self, stmt: &mut Stmt<'src>) {
+        walk_statement_mut(self, stmt)
+    }
+
+    fn visit_function_mut(&mut self, declaration: &mut FunctionDecl<'src>) {
+        walk_function_mut(self, declaration)
+    }
+}
+
+pub fn walk_expression_mut<'src, V: MutVisitor<'src>>(
+    visitor: &mut V,
+    expr: &mut Expression<'src>,
+) {
+    match expr {
+        Expression::Unary { right, .. } => visitor.visit_expression_mut(right),
+        Expression::Binary { left, right, .. } | Expression::Logical { left, right, .. } => {
+            visitor.visit_expression_mut(left);
+            visitor.visit_expression_mut(right);
+        }
+        Expression::Grouping { expression, .. } => visitor.visit_expression_mut(expression),
+        Expression::Call {
+            callee, arguments, ..
+        } => {
+            visitor.visit_expression_mut(callee);
+            for argument in arguments.iter_mut() {
+                visitor.visit_expression_mut(argument);
+            }
+        }
+        Expression::Get { object, .. } => visitor.visit_expression_mut(object),
+        Expression::Set { object, value, .. } => {
+            visitor.visit_expression_mut(object);
+            visitor.visit_expression_mut(value);
+        }
+        Expression::Assign { value, .. } => visitor.visit_expression_mut(value),
+        Expression::Literal(_)
+        | Expression::Super { .. }
+        | Expression::This { .. }
+        | Expression::Variable { .. } => {}
+    }
+}
+
+pub fn walk_statement_mut<'src, V: MutVisitor<'src>>(visitor: &mut V, stmt: &mut Stmt<'src>) {
+    match stmt {
+        Stmt::Expression(expr) | Stmt::Print(expr) => visitor.visit_expression_mut(expr),
+        Stmt::Function(declaration) => visitor.visit_function_mut(Rc::make_mut(declaration)),
+        Stmt::Return { value, .. } => {
+            if let Some(value) = value {
+                visitor.visit_expression_mut(value);
+            }
+        }
+        Stmt::Var { initializer, .. } => {
+            if let Some(initializer) = initializer {
+                visitor.visit_expression_mut(initializer);
+            }
+        }
+        Stmt::Block(statements) => {
+            for stmt in statements.iter_mut() {
+                visitor.visit_statement_mut(stmt);
+            }
+        }
+        Stmt::Class {
+            superclass,
+            methods,
+            ..
+        } => {
+            if let Some(superclass) = superclass {
+                visitor.visit_expression_mut(superclass);
+            }
+            for method in methods.iter_mut() {
+                visitor.visit_function_mut(Rc::make_mut(method));
+            }
+        }
+        Stmt::If {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            visitor.visit_expression_mut(condition);
+            visitor.visit_statement_mut(then_branch);
+            if let Some(else_branch) = else_branch {
+                visitor.visit_statement_mut(else_branch);
+            }
+        }
+        Stmt::While { condition, body } => {
+            visitor.visit_expression_mut(condition);
+            visitor.visit_statement_mut(body);
+        }
+    }
+}
+
+pub fn walk_function_mut<'src, V: MutVisitor<'src>>(
+    visitor: &mut V,
+    declaration: &mut FunctionDecl<'src>,
+) {
+    for stmt in declaration.body.iter_mut() {
+        visitor.visit_statement_mut(stmt);
+    }
+}
+
+// rebuilds the syntax tree from owned nodes, so a node can be replaced by one of a
+// different kind. The `walk_..._fold` functions fold the children and reassemble
+// the node around them.
+pub trait Fold<'src>: Sized {
+    fn fold_expression(&mut self, expr: Expression<'src>) -> Expression<'src> {
+        walk_expression_fold(self, expr)
+    }
+
+    fn fold_statement(&mut self, stmt: Stmt<'src>) -> Stmt<'src> {
+        walk_statement_fold(self, stmt)
+    }
+
+    fn fold_function(&mut self, declaration: FunctionDecl<'src>) -> FunctionDecl<'src> {
+        walk_function_fold(self, declaration)
+    }
+}
+
+pub fn walk_expression_fold<'src, F: Fold<'src>>(
+    folder: &mut F,
+    expr: Expression<'src>,
+) -> Expression<'src> {
+    let mut fold = |expr: Box<Expression<'src>>| Box::new(folder.fold_expression(*expr));
+    match expr {
+        Expression::Unary {
+            operator,
+            right,
+            span,
+        } => Expression::Unary {
+            operator,
+            right: fold(right),
+            span,
+        },
+        Expression::Binary {
+            left,
+            operator,
+            right,
+            span,
+        } => Expression::Binary {
+            left: fold(left),
+            operator,
+            right: fold(right),
+            span,
+        },
+        Expression::Logical {
+            left,
+            operator,
+            right,
+            span,
+        } => Expression::Logical {
+            left: fold(left),
+            operator,
+            right: fold(right),
+            span,
+        },
+        Expression::Grouping { expression, span } => Expression::Grouping {
+            expression: fold(expression),
+            span,
+        },
+        Expression::Call {
+            callee,
+            paren,
+            arguments,
+            span,
+        } => Expression::Call {
+            callee: fold(callee),
+            paren,
+            arguments: arguments
+                .into_iter()
+                .map(|argument| folder.fold_expression(argument))
+                .collect(),
+            span,
+        },
+        Expression::Get { object, name, span } => Expression::Get {
+            object: fold(object),
+            name,
+            span,
+        },
+        Expression::Set {
+            object,
+            name,
+            value,
+            span,
+        } => Expression::Set {
+            object: fold(object),
+            name,
+            value: fold(value),
+            span,
+        },
+        Expression::Assign {
+            name,
+            value,
+            span,
+            depth,
+        } => Expression::Assign {
+            name,
+            value: fold(value),
+            span,
+            depth,
+        },
+        expr @ (Expression::Literal(_)
+        | Expression::Super { .. }
+        | Expression::This { .. }
+        | Expression::Variable { .. }) => expr,
+    }
+}
+
+pub fn walk_statement_fold<'src, F: Fold<'src>>(folder: &mut F, stmt: Stmt<'src>) -> Stmt<'src> {
+    match stmt {
+        Stmt::Expression(expr) => Stmt::Expression(folder.fold_expression(expr)),
+        Stmt::Print(expr) => Stmt::Print(folder.fold_expression(expr)),
+        Stmt::Function(declaration) => Stmt::Function(fold_shared_function(folder, declaration)),
+        Stmt::Return { keyword, value } => Stmt::Return {
+            keyword,
+            value: value.map(|value| folder.fold_expression(value)),
+        },
+        Stmt::Var { name, initializer } => Stmt::Var {
+            name,
+            initializer: initializer.map(|initializer| folder.fold_expression(initializer)),
+        },
+        Stmt::Block(statements) => Stmt::Block(
+            statements
+                .into_iter()
+                .map(|stmt| folder.fold_statement(stmt))
+                .collect(),
+        ),
+        Stmt::Class {
+            name,
+            superclass,
+            methods,
+        } => Stmt::Class {
+            name,
+            superclass: superclass.map(|superclass| folder.fold_expression(superclass)),
+            methods: methods
+                .into_iter()
+                .map(|method| fold_shared_function(folder, method))
+                .collect(),
+        },
+        Stmt::If {
+            condition,
+            then_branch,
+            else_branch,
+        } => Stmt::If {
+            condition: folder.fold_expression(condition),
+            then_branch: Box::new(folder.fold_statement(*then_branch)),
+            else_branch: else_branch
+                .map(|else_branch| Box::new(folder.fold_statement(*else_branch))),
+        },
+        Stmt::While { condition, body } => Stmt::While {
+            condition: folder.fold_expression(condition),
+            body: Box::new(folder.fold_statement(*body)),
+        },
+    }
+}
+
+pub fn walk_function_fold<'src, F: Fold<'src>>(
+    folder: &mut F,
+    declaration: FunctionDecl<'src>,
+) -> FunctionDecl<'src> {
+    FunctionDecl {
+        name: declaration.name,
+        params: declaration.params,
+        body: declaration
+            .body
+            .into_iter()
+            .map(|stmt| folder.fold_statement(stmt))
+            .collect(),
+    }
+}
+
+// declarations are shared with closures once the program runs, in which case the
+// folded function is a copy
+fn fold_shared_function<'src, F: Fold<'src>>(
+    folder: &mut F,
+    declaration: Rc<FunctionDecl<'src>>,
+) -> Rc<FunctionDecl<'src>> {
+    let declaration = Rc::try_unwrap(declaration).unwrap_or_else(|shared| (*shared).clone());
+    Rc::new(folder.fold_function(declaration))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::lexer::scan_tokens;
+    use crate::scanner::token::Literal;
+    use crate::syntax_tree::parser::Parser;
+
+    fn parse(source: &str) -> Vec<Stmt<'_>> {
+        let (statements, errors) = Parser::new(scan_tokens(source).unwrap()).parse();
+        assert!(errors.is_empty(), "unexpected parse errors: {:?}", errors);
+        statements
+    }
+
+    // collects the names of all variables read, relying on the default walk
+    #[derive(Default)]
+    struct VariableNames(Vec<String>);
+
+    impl<'src> Visitor<'src> for VariableNames {
+        type Output = ();
+
+        fn visit_expression(&mut self, expr: &Expression<'src>) {
+            if let Expression::Variable { name, .. } = expr {
+                self.0.push(name.name().to_string());
+            }
+            walk_expression(self, expr)
+        }
+    }
+
+    #[test]
+    fn test_visitor_reaches_every_node() {
+        let statements = parse(
+            "var a = b; fun f(x) { return c + x; } class C < D { m() { print e.f(g); } } \
+             if (h) { while (i) j = k; } else l.m = n or -o;",
+        );
+
+        let mut names = VariableNames::default();
+        for stmt in statements.iter() {
+            names.visit_statement(stmt);
+        }
+
+        let expected = ["b", "c", "x", "D", "e", "g", "h", "i", "k", "l", "n", "o"];
+        assert_eq!(names.0, expected);
+    }
+
+    // counts nodes by returning a value from every expression
+    struct Size;
+
+    impl<'src> Visitor<'src> for Size {
+        type Output = usize;
+
+        fn visit_expression(&mut self, expr: &Expression<'src>) -> usize {
+            match expr {
+                Expression::Binary { left, right, .. } => {
+                    1 + self.visit_expression(left) + self.visit_expression(right)
+                }
+                Expression::Grouping { expression, .. } => 1 + self.visit_expression(expression),
+                _ => 1,
+            }
+        }
+    }
+
+    #[test]
+    fn test_visitor_returns_values() {
+        let tokens = scan_tokens("(1 + 2) * 3").unwrap();
+        let expr = Parser::new(tokens).parse_expression().unwrap();
+
+        assert_eq!(Size.visit_expression(&expr), 6);
+    }
+
+    #[derive(Default)]
+    struct Numbers(Vec<f64>);
+
+    impl<'src> Visitor<'src> for Numbers {
+        type Output = ();
+
+        fn visit_expression(&mut self, expr: &Expression<'src>) {
+            if let Expression::Literal(token) = expr {
+                if let Some(Literal::Numeric(n)) = token.literal {
+                    self.0.push(n);
+                }
+            }
+            walk_expression(self, expr)
+        }
+    }
+
+    // negates every number literal in place
+    struct Negate;
+
+    impl<'src> MutVisitor<'src> for Negate {
+        fn visit_expression_mut(&mut self, expr: &mut Expression<'src>) {
+            if let Expression::Literal(token) = expr {
+                if let Some(Literal::Numeric(n)) = token.literal.as_mut() {
+                    *n = -*n;
+                }
+            }
+            walk_expression_mut(self, expr)
+        }
+    }
+
+    #[test]
+    fn test_mut_visitor_rewrites_in_place() {
+        let mut statements = parse("fun f() { return 1 + g(2); } print 3;");
+
+        for stmt in statements.iter_mut() {
+            Negate.visit_statement_mut(stmt);
+        }
+
+        let mut numbers = Numbers::default();
+        for stmt in statements.iter() {
+            numbers.visit_statement(stmt);
+        }
+        assert_eq!(numbers.0, [-1.0, -2.0, -3.0]);
+    }
+
+    // replaces every grouping by the expression inside it
+    struct Ungroup;
+
+    impl<'src> Fold<'src> for Ungroup {
+        fn fold_expression(&mut self, expr: Expression<'src>) -> Expression<'src> {
+            match expr {
+                Expression::Grouping { expression, .. } => self.fold_expression(*expression),
+                expr => walk_expression_fold(self, expr),
+            }
+        }
+    }
+
+    #[test]
+    fn test_fold_replaces_nodes() {
+        let statements = parse("var a = ((1)) * (f((2)));");
+
+        let folded: Vec<Stmt> = statements
+            .into_iter()
+            .map(|stmt| Ungroup.fold_statement(stmt))
+            .collect();
+
+        let Stmt::Var {
+            initializer: Some(Expression::Binary { left, right, .. }),
+            ..
+        } = &folded[0]
+        else {
+            panic!("expected a multiplication, got {:?}", folded);
+        };
+        assert!(matches!(**left, Expression::Literal(_)));
+        let Expression::Call { arguments, .. } = &**right else {
+            panic!("expected a call, got {:?}", right);
+        };
+        assert!(matches!(arguments[0], Expression::Literal(_)));
+    }
+}